@@ -0,0 +1,26 @@
+#! /bin/sh
+
+#**aw-fm-settings-begin**
+# name=Split Parent
+# interactive=true
+#**aw-fm-settings-end**
+
+# Opens the parent directory in a new pane and reports failures.
+# Requires jq.
+
+[ -n "$AWFM_CURRENT_TAB_PATH" ] || exit 0
+
+echo "Split horizontal"
+read -r response
+
+if [ "$(echo "$response" | jq .ok)" != "true" ]; then
+  echo "$response" | jq -r .error >&2
+  exit 1
+fi
+
+tab=$(echo "$response" | jq .tab.id)
+
+jq -cn --arg cmd "Navigate $(dirname "$AWFM_CURRENT_TAB_PATH")" --argjson tab "$tab" \
+  '{command: $cmd, tab: $tab}'
+read -r response
+echo "$response" | jq -e .ok > /dev/null
@@ -0,0 +1,22 @@
+#! /bin/sh
+
+#**aw-fm-settings-begin**
+# name=Checksum Files
+# parse_output=true
+# selection_via=args
+# selection=at_least_1
+#**aw-fm-settings-end**
+
+# Writes a SHA256SUMS file next to the selected files, reporting progress as it goes.
+
+total=$#
+done=0
+
+echo "Status Checksumming $total file(s)"
+
+for f in "$@"; do
+  [ -f "$f" ] || continue
+  sha256sum "$f" >> "$(dirname "$f")/SHA256SUMS"
+  done=$((done + 1))
+  echo "Progress $(awk "BEGIN { print $done / $total }") $(basename "$f")"
+done
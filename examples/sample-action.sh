@@ -89,10 +89,62 @@
 #
 ## Whether to read stdout of the script as a series of commands to execute.
 ## Use this to have the script control something in aw-fm.
+## "Progress <fraction> [message]" and "Status <message>" are shown as soon as they're printed,
+## everything else runs once the script exits successfully.
 ## true/false
 #
 # parse_output=false
 #
+## Like parse_output, but runs each command as soon as it is printed and writes a line of JSON
+## back to stdin for each one, like {"ok":true,"tab":{...}} or {"ok":false,"error":"..."}.
+## Lines can also be JSON requests like {"command":"Refresh","tab":3} or {"query":"selection"}.
+## Can't be combined with selection_via=stdin0.
+## true/false
+#
+# interactive=false
+#
+## How the selected files are passed to the script.
+## 'env' sets the newline-separated AWFM_SELECTION environment variable.
+## 'args' passes each selected file as a separate argument.
+## 'stdin0' writes the selected files to stdin, each terminated by a NUL byte.
+## With 'args' or 'stdin0' AWFM_SELECTION is not set. Prefer them for large selections or
+## when file names may contain newlines.
+#
+# selection_via=env
+#
+## A submenu or section of the context menu to place this action in.
+## Shared with context_menu entries in aw-fm.toml with the same name.
+## Only one of submenu or section can be set.
+#
+# submenu=
+# section=
+#
+## An icon to show next to the name, either an icon name from the theme or a path to an image.
+#
+# icon=
+#
+## A keyboard shortcut that runs this action against the current selection in the active tab.
+## Modifiers and a key joined by "+", using the same names as shortcuts in aw-fm.toml.
+## Shortcuts in aw-fm.toml take precedence and conflicts are reported when actions are loaded.
+## The action is still subject to the filters above and does nothing if they don't match.
+#
+# shortcut=Control+Shift+E
+#
+## A message to show in a confirmation dialog, listing the selected files, before running.
+## Useful for destructive actions.
+#
+# confirm=
+#
+## Whether to run inside the terminal emulator configured as "terminal" in aw-fm.toml.
+## Can't be combined with parse_output, interactive, or selection_via=stdin0.
+## true/false
+#
+# terminal=false
+#
+## How many seconds to let the action run before killing it. 0 means no limit.
+#
+# timeout=0
+#
 #**aw-fm-settings-end**
 
 
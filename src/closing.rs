@@ -17,6 +17,7 @@ type CloseSender = Mutex<Option<Sender<()>>>;
 type CloseReceiver = Receiver<()>;
 
 static CLOSED: LazyLock<Arc<AtomicBool>> = LazyLock::new(|| Arc::new(AtomicBool::new(false)));
+static CRASHED: AtomicBool = AtomicBool::new(false);
 static CLOSER: LazyLock<(CloseSender, CloseReceiver)> = LazyLock::new(|| {
     let (s, r) = bounded::<()>(1);
     (Mutex::new(Option::Some(s)), r)
@@ -48,6 +49,11 @@ pub fn closed() -> bool {
     CLOSED.load(Ordering::Relaxed)
 }
 
+// True if the application is closing because of a fatal error.
+pub fn crashed() -> bool {
+    CRASHED.load(Ordering::Relaxed)
+}
+
 pub async fn closed_fut() {
     // We only care that it's closed.
     let _ignored = CLOSER.1.recv().await;
@@ -78,6 +84,8 @@ pub fn fatal(msg: impl AsRef<str>) {
 
     error!("{msg}");
 
+    CRASHED.store(true, Ordering::Relaxed);
+
     if close() {
         let path = temp_dir().join(format!("aw-fm_crash_{}", process::id()));
         let Ok(mut file) = std::fs::File::options().write(true).create_new(true).open(&path) else {
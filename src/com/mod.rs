@@ -7,17 +7,20 @@ use std::fmt;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::atomic::AtomicBool;
+use std::time::Duration;
 
 use ahash::AHashSet;
 use derive_more::{Deref, DerefMut, From};
 use gtk::EventController;
 use gtk::glib::{Object, SignalHandlerId};
 use gtk::prelude::{EventControllerExt, IsA, ObjectExt, WidgetExt};
+use strum_macros::EnumString;
 use tokio::sync::oneshot;
 
 pub use self::entry::*;
 pub use self::settings::*;
 pub use self::snapshot::*;
+use crate::config::CONFIG;
 use crate::gui::TabId;
 
 
@@ -71,26 +74,86 @@ pub enum ActionTarget {
     Active,
 }
 
+// Where a completion was requested from
+#[derive(Debug, Clone, Copy)]
+pub enum CompletionTarget {
+    // The location bar of a tab
+    Tab(TabId),
+    CommandLine,
+}
+
 #[derive(Debug)]
 pub struct CompletionResult {
-    pub tab: TabId,
+    pub target: CompletionTarget,
     pub initial: String,
     pub candidates: Vec<PathBuf>,
     pub position: usize,
 }
 
+// How custom actions receive the selected files
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum SelectionVia {
+    // Newline-separated in AWFM_SELECTION
+    #[default]
+    Env,
+    // One argument per file
+    Args,
+    // NUL-separated on stdin
+    Stdin0,
+}
+
+// Fixed when a search starts, so saved searches can keep the options they were saved with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchOptions {
+    pub max_depth: Option<u8>,
+    pub show_all: bool,
+}
+
+impl SearchOptions {
+    pub fn configured() -> Self {
+        Self {
+            max_depth: CONFIG.search_max_depth,
+            show_all: CONFIG.search_show_all,
+        }
+    }
+}
+
+// The environment for commands from configs/scripts
+#[derive(Debug)]
+pub struct ExecContext {
+    pub env: Vec<(String, OsString)>,
+    // Unset unless run_in_tab_directory is enabled
+    pub cwd: Option<Arc<Path>>,
+    pub selection_via: SelectionVia,
+    // Empty when selection_via is Env, it's in the environment instead
+    pub selection: Vec<Arc<Path>>,
+    // Run inside the configured terminal emulator
+    pub terminal: bool,
+    // Killed if it runs for longer than this
+    pub timeout: Option<Duration>,
+    // Send output to the scripts panel. Execute otherwise inherits stdout and stderr, the output
+    // of scripts is still read but not shown.
+    pub capture_output: bool,
+}
+
 #[derive(Debug)]
 pub enum ManagerAction {
     Open(Arc<Path>, SortSettings, Arc<AtomicBool>),
     Unwatch(Arc<Path>),
-    Search(Arc<Path>, Arc<AtomicBool>),
-    EndSearch(Arc<AtomicBool>),
+    Search(Arc<Path>, SearchOptions, Arc<AtomicBool>),
+    EndSearch(Arc<AtomicBool>, SearchOptions),
 
     // For commands from configs/scripts
-    Execute(Arc<Path>, Vec<(String, OsString)>),
-    Script(Arc<Path>, ActionTarget, Vec<(String, OsString)>),
+    Execute(Arc<Path>, ExecContext),
+    Script(Arc<Path>, ActionTarget, ExecContext),
+    // Like Script, but each command gets a response on stdin
+    InteractiveScript(Arc<Path>, ActionTarget, ExecContext),
     // When launching an application or executable directly
     Launch(Arc<Path>, Vec<(String, OsString)>),
+    // Kills a running Execute, Script or interactive script
+    KillScript(u64),
+    KillScripts,
 
     GetChildren(Vec<Arc<Path>>, Arc<AtomicBool>),
 
@@ -100,7 +163,8 @@ pub enum ManagerAction {
         finished: oneshot::Sender<AHashSet<Arc<Path>>>,
     },
 
-    Complete(PathBuf, String, TabId),
+    // Includes frecency scores of visited directories for ranking
+    Complete(PathBuf, String, CompletionTarget, Arc<[(Arc<Path>, f64)]>),
     CancelCompletion,
 }
 
@@ -136,7 +200,19 @@ pub enum GuiAction {
     ConveyWarning(String),
 
     Action(String, ActionTarget),
+    // Responds with a single line of JSON
+    InteractiveAction(String, ActionTarget, oneshot::Sender<String>),
     Completion(CompletionResult),
+    // Scripts in the custom actions directory that were added, changed or removed
+    CustomActionsChanged(Vec<Arc<Path>>),
+    ConfigChanged,
+
+    // Processes that can be killed from the scripts panel, with their output line by line
+    ScriptStarted(u64, String),
+    ScriptOutput(u64, String),
+    // From Progress and Status lines, the fraction is unset for Status
+    ScriptProgress(u64, Option<f64>, String),
+    ScriptFinished(u64),
 
     Quit,
 }
@@ -3,11 +3,11 @@ use std::cmp::Ordering;
 use gtk::SortType;
 use gtk::glib::Object;
 use gtk::prelude::Cast;
-use strum_macros::{AsRefStr, EnumString};
+use strum_macros::{AsRefStr, EnumString, VariantNames};
 
 use super::EntryObject;
 
-#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, EnumString, AsRefStr)]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, EnumString, AsRefStr, VariantNames)]
 #[strum(serialize_all = "lowercase")]
 pub enum DisplayMode {
     #[default]
@@ -15,7 +15,7 @@ pub enum DisplayMode {
     Columns,
 }
 
-#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, EnumString, AsRefStr)]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, EnumString, AsRefStr, VariantNames)]
 #[strum(serialize_all = "lowercase")]
 pub enum SortMode {
     #[default]
@@ -24,7 +24,7 @@ pub enum SortMode {
     Size,
 }
 
-#[derive(Debug, PartialEq, Eq, Default, Clone, Copy, EnumString, AsRefStr)]
+#[derive(Debug, PartialEq, Eq, Default, Clone, Copy, EnumString, AsRefStr, VariantNames)]
 #[strum(serialize_all = "lowercase")]
 pub enum SortDir {
     #[default]
@@ -1,9 +1,11 @@
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fmt;
 use std::num::NonZeroU64;
+use std::ops::Deref;
 use std::path::PathBuf;
 use std::str::FromStr;
-use std::sync::{Arc, LazyLock};
+use std::sync::{Arc, LazyLock, RwLock};
 
 use clap::Parser;
 use dirs::config_dir;
@@ -109,6 +111,18 @@ pub struct Opt {
     #[arg(long)]
     pub empty: bool,
 
+    /// Send a command or JSON request to a running instance and print the response
+    #[arg(long, value_name = "REQUEST")]
+    pub send: Option<String>,
+
+    /// The IPC socket for --send. Defaults to $AWFM_SOCKET or the newest running instance
+    #[arg(long, requires = "send")]
+    pub socket: Option<PathBuf>,
+
+    /// Check the config and the custom actions directory for problems, then exit
+    #[arg(long)]
+    pub check_config: bool,
+
     #[command(subcommand)]
     pub chooser_mode: Option<ChooserCommand>,
 }
@@ -118,6 +132,8 @@ pub struct Shortcut {
     pub action: String,
     pub key: String,
     pub modifiers: Option<String>,
+    #[serde(default)]
+    pub mode: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -230,7 +246,15 @@ impl TryFrom<String> for Selection {
     }
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RestoreSession {
+    #[default]
+    Never,
+    Last,
+}
+
+#[derive(Debug, Deserialize, Default, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum NfsPolling {
     #[default]
@@ -268,6 +292,10 @@ pub struct Config {
     pub context_menu: Vec<ContextMenuEntry>,
     #[serde(default)]
     pub mouse_buttons: Vec<MouseButtonAction>,
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
+    #[serde(default, deserialize_with = "zero_is_none")]
+    pub key_sequence_timeout: Option<NonZeroU64>,
 
     #[serde(default)]
     pub directory_collisions: DirectoryCollision,
@@ -276,6 +304,10 @@ pub struct Config {
 
     #[serde(default, deserialize_with = "empty_path_is_none")]
     pub actions_directory: Option<Arc<PathBuf>>,
+    #[serde(default)]
+    pub run_in_tab_directory: bool,
+    #[serde(default)]
+    pub terminal: Vec<String>,
 
     #[serde(default)]
     pub normalize_names: bool,
@@ -286,6 +318,14 @@ pub struct Config {
     #[serde(default, deserialize_with = "empty_path_is_none")]
     pub database: Option<PathBuf>,
 
+    #[serde(default)]
+    pub restore_session: RestoreSession,
+
+    #[serde(default)]
+    pub ipc_socket: bool,
+    #[serde(default)]
+    pub watch_config: bool,
+
     #[serde(default)]
     pub nfs_polling: NfsPolling,
 
@@ -381,23 +421,85 @@ pub static SINGLE_SELECTION: LazyLock<bool> = LazyLock::new(|| {
 
 static DEFAULT_CONFIG: &str = include_str!("../aw-fm.toml.sample");
 
-pub static CONFIG: LazyLock<Config> = LazyLock::new(|| {
+// Each loaded config is leaked so anything borrowed from CONFIG stays valid after a reload,
+// it just goes stale. Reloads are rare and configs are small.
+pub struct LiveConfig {
+    current: RwLock<&'static Config>,
+    // Where the config was loaded from, if not the defaults
+    pub path: Option<PathBuf>,
+}
+
+impl Deref for LiveConfig {
+    type Target = Config;
+
+    fn deref(&self) -> &Config {
+        *self.current.read().unwrap()
+    }
+}
+
+pub fn load_config() -> Result<(Config, Option<PathBuf>), String> {
     match awconf::load_config::<Config>("aw-fm", OPTIONS.awconf.as_ref(), Some(DEFAULT_CONFIG)) {
         Ok((conf, Some(path))) => {
             info!("Loaded config from {path:?}");
-            conf
+            Ok((conf, Some(path)))
         }
         Ok((conf, None)) => {
             info!("Loaded default config");
-            conf
+            Ok((conf, None))
         }
         Err(e) => {
             error!("Error loading config: {e}");
-            panic!("Error loading config: {e}");
+            Err(e.to_string())
         }
     }
+}
+
+pub static CONFIG: LazyLock<LiveConfig> = LazyLock::new(|| match load_config() {
+    Ok((conf, path)) => LiveConfig { current: RwLock::new(Box::leak(Box::new(conf))), path },
+    Err(e) => panic!("Error loading config: {e}"),
 });
 
+// Returns the previous config. On failure the current config is left in place.
+pub fn reload_config() -> Result<&'static Config, String> {
+    let (conf, _) = load_config()?;
+    let mut current = CONFIG.current.write().unwrap();
+    Ok(std::mem::replace(&mut *current, Box::leak(Box::new(conf))))
+}
+
+impl Config {
+    // Settings that are only read once at startup.
+    pub fn restart_required(&self, new: &Self) -> Vec<&'static str> {
+        let mut changed = Vec::new();
+
+        macro_rules! check {
+            ($($field:ident),+) => {
+                $(if self.$field != new.$field {
+                    changed.push(stringify!($field));
+                })+
+            };
+        }
+
+        // Anything copied into a static, like ACTIONS_DIR, DIALOG_RES, or the thread-local in
+        // natsort, is never updated and belongs here too.
+        check!(
+            single_window,
+            dialog_resolution,
+            actions_directory,
+            database,
+            ipc_socket,
+            watch_config,
+            nfs_polling,
+            unload_timeout,
+            max_thumbnailers,
+            background_thumbnailers,
+            force_small_thumbnails,
+            disable_filemanager_dbus,
+            normalize_names
+        );
+
+        changed
+    }
+}
 
 pub fn init() {
     LazyLock::force(&OPTIONS);
@@ -1,19 +1,23 @@
 use std::cell::Cell;
 use std::ffi::OsStr;
+use std::fmt;
 use std::os::unix::prelude::OsStrExt;
 use std::path::Path;
 use std::sync::Arc;
 use std::sync::mpsc::{Receiver, SyncSender};
 use std::thread::JoinHandle;
-use std::time::Instant;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 use dirs::data_dir;
 use rusqlite::types::{FromSql, FromSqlError};
-use rusqlite::{Connection, ToSql, params};
-use serde::{Deserialize, Serialize};
+use rusqlite::{Connection, ToSql, Transaction, TransactionBehavior, params};
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use tokio::sync::oneshot;
 
-use crate::com::{DebugIgnore, DirSettings, DisplayMode, SortDir, SortMode, SortSettings};
+use crate::com::{
+    DebugIgnore, DirSettings, DisplayMode, SearchOptions, SortDir, SortMode, SortSettings,
+};
 use crate::config::CONFIG;
 use crate::{closing, spawn_thread};
 
@@ -41,6 +45,9 @@ pub struct SavedSplit {
     pub horizontal: bool,
     pub start: SplitChild,
     pub end: SplitChild,
+    // Absent in sessions saved by older versions
+    #[serde(default)]
+    pub position: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -49,10 +56,87 @@ pub struct SavedGroup {
     pub split: SavedSplit,
 }
 
+// Paths are stored as raw bytes since they may not be valid UTF-8.
+#[derive(Debug, Clone)]
+pub struct SavedPath(pub Arc<Path>);
+
+impl Serialize for SavedPath {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0.as_os_str().as_bytes())
+    }
+}
+
+impl<'de> Deserialize<'de> for SavedPath {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct PathVisitor;
+
+        impl Visitor<'_> for PathVisitor {
+            type Value = SavedPath;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a path as raw bytes")
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+                Ok(SavedPath(Path::new(OsStr::from_bytes(v)).into()))
+            }
+        }
+
+        deserializer.deserialize_bytes(PathVisitor)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedScroll {
+    pub path: SavedPath,
+    pub index: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedFocus {
+    pub path: SavedPath,
+    pub select: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedHistory {
+    pub location: SavedPath,
+    pub search: Option<String>,
+    pub scroll: Option<SavedScroll>,
+    pub focus: Option<SavedFocus>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedTab {
+    pub current: SavedHistory,
+    pub past: Vec<SavedHistory>,
+    pub future: Vec<SavedHistory>,
+    pub selection: Vec<SavedPath>,
+}
+
 #[derive(Debug)]
 pub struct Session {
     pub paths: Vec<Arc<Path>>,
     pub groups: Vec<SavedGroup>,
+    // Empty for sessions saved by older versions, in which case only paths are restored.
+    pub tabs: Vec<SavedTab>,
+}
+
+// Only the primary instance, the first one started while no other primary is running, restores
+// and autosaves the last session.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct InstanceState {
+    pub primary: bool,
+    // The previous primary instance exited without marking itself clean
+    pub crashed: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct SavedSearch {
+    pub name: String,
+    pub root: Arc<Path>,
+    pub query: String,
+    pub options: SearchOptions,
 }
 
 enum DBAction {
@@ -61,6 +145,16 @@ enum DBAction {
     LoadSession(String, oneshot::Sender<Option<Session>>),
     SaveSession(String, Session),
     DeleteSession(String),
+    ListSessions(oneshot::Sender<Vec<String>>),
+    ListSearches(oneshot::Sender<Vec<SavedSearch>>),
+    SaveSearch(SavedSearch),
+    DeleteSearch(String),
+    Visit(Arc<Path>),
+    RecentDirs(oneshot::Sender<Vec<(Arc<Path>, f64)>>),
+    AddCommand(String),
+    CommandHistory(oneshot::Sender<Vec<String>>),
+    MarkRunning(oneshot::Sender<InstanceState>),
+    MarkClean,
     Teardown,
 }
 
@@ -146,6 +240,87 @@ impl DBCon {
         self.0.send(DBAction::DeleteSession(name)).unwrap();
     }
 
+    pub fn session_names(&self) -> Vec<String> {
+        let start = Instant::now();
+
+        let (send, recv) = oneshot::channel();
+
+        self.0.send(DBAction::ListSessions(send)).unwrap();
+
+        // This should swallow all DB errors so should not fail
+        let names = recv.blocking_recv().unwrap();
+
+        trace!("Listed {} saved sessions in {:?}", names.len(), start.elapsed());
+        names
+    }
+
+    pub fn saved_searches(&self) -> Vec<SavedSearch> {
+        let start = Instant::now();
+
+        let (send, recv) = oneshot::channel();
+
+        self.0.send(DBAction::ListSearches(send)).unwrap();
+
+        // This should swallow all DB errors so should not fail
+        let searches = recv.blocking_recv().unwrap();
+
+        trace!("Loaded {} saved searches in {:?}", searches.len(), start.elapsed());
+        searches
+    }
+
+    pub fn save_search(&self, search: SavedSearch) {
+        self.0.send(DBAction::SaveSearch(search)).unwrap();
+    }
+
+    pub fn delete_search(&self, name: String) {
+        self.0.send(DBAction::DeleteSearch(name)).unwrap();
+    }
+
+    pub fn visit(&self, path: Arc<Path>) {
+        self.0.send(DBAction::Visit(path)).unwrap();
+    }
+
+    // Visited directories with their frecency scores, highest first.
+    // Scoring the whole table is too slow to block the GUI on, so callers await the result.
+    pub fn recent_dirs(&self) -> oneshot::Receiver<Vec<(Arc<Path>, f64)>> {
+        let (send, recv) = oneshot::channel();
+
+        self.0.send(DBAction::RecentDirs(send)).unwrap();
+        recv
+    }
+
+    pub fn add_command(&self, command: String) {
+        self.0.send(DBAction::AddCommand(command)).unwrap();
+    }
+
+    // Commands run from the command line, oldest first.
+    pub fn command_history(&self) -> Vec<String> {
+        let start = Instant::now();
+
+        let (send, recv) = oneshot::channel();
+
+        self.0.send(DBAction::CommandHistory(send)).unwrap();
+
+        // This should swallow all DB errors so should not fail
+        let history = recv.blocking_recv().unwrap();
+
+        trace!("Loaded {} commands from history in {:?}", history.len(), start.elapsed());
+        history
+    }
+
+    pub fn mark_running(&self) -> InstanceState {
+        let (send, recv) = oneshot::channel();
+
+        self.0.send(DBAction::MarkRunning(send)).unwrap();
+
+        // This should swallow all DB errors so should not fail
+        recv.blocking_recv().unwrap()
+    }
+
+    pub fn mark_clean(&self) {
+        self.0.send(DBAction::MarkClean).unwrap();
+    }
+
     pub fn destroy(&self) {
         debug!("Tearing down database connection");
         self.0.send(DBAction::Teardown).unwrap();
@@ -165,6 +340,16 @@ impl Con {
                 DBAction::SaveSession(name, session) => self.save_session(&name, session),
                 DBAction::LoadSession(name, resp) => drop(resp.send(self.load_session(&name))),
                 DBAction::DeleteSession(name) => self.delete_session(&name),
+                DBAction::ListSessions(resp) => drop(resp.send(self.session_names())),
+                DBAction::ListSearches(resp) => drop(resp.send(self.saved_searches())),
+                DBAction::SaveSearch(search) => self.save_search(search),
+                DBAction::DeleteSearch(name) => self.delete_search(&name),
+                DBAction::Visit(path) => self.visit(&path),
+                DBAction::RecentDirs(resp) => drop(resp.send(self.recent_dirs())),
+                DBAction::AddCommand(command) => self.add_command(&command),
+                DBAction::CommandHistory(resp) => drop(resp.send(self.command_history())),
+                DBAction::MarkRunning(resp) => drop(resp.send(self.mark_running())),
+                DBAction::MarkClean => self.mark_clean(),
                 DBAction::Teardown => {
                     return;
                 }
@@ -239,7 +424,7 @@ VALUES
     fn load_session(&self, name: &str) -> Option<Session> {
         let con = &self.0;
 
-        con.query_row("SELECT paths, groups FROM sessions WHERE name = ?", [name], |row| {
+        con.query_row("SELECT paths, groups, tabs FROM sessions WHERE name = ?", [name], |row| {
             let raw: &[u8] = row.get_ref(0)?.as_bytes()?;
 
             let paths = raw
@@ -261,7 +446,19 @@ VALUES
                 Vec::new()
             };
 
-            Ok(Session { paths, groups })
+            let tabs = if let Some(raw) = row.get_ref(2)?.as_blob_or_null()? {
+                match rmp_serde::from_slice(raw) {
+                    Ok(ts) => ts,
+                    Err(e) => {
+                        error!("Error deserializing saved tabs: {e}");
+                        Vec::new()
+                    }
+                }
+            } else {
+                Vec::new()
+            };
+
+            Ok(Session { paths, groups, tabs })
         })
         .map_err(|e| {
             if e == rusqlite::Error::QueryReturnedNoRows {
@@ -289,10 +486,11 @@ VALUES
         // forward compatible
         // Should never fail.
         let groups = rmp_serde::to_vec(&session.groups).unwrap();
+        let tabs = rmp_serde::to_vec(&session.tabs).unwrap();
 
         con.execute(
-            "INSERT OR REPLACE INTO sessions(name, paths, groups) VALUES (?, ?, ?);",
-            params![name, paths, groups],
+            "INSERT OR REPLACE INTO sessions(name, paths, groups, tabs) VALUES (?, ?, ?, ?);",
+            params![name, paths, groups, tabs],
         )
         .unwrap_or_else(|e| {
             if e == rusqlite::Error::QueryReturnedNoRows {
@@ -314,6 +512,257 @@ VALUES
 
         trace!("Deleted session {name} in {:?}", start.elapsed());
     }
+
+    fn visit(&self, path: &Path) {
+        let con = &self.0;
+        let now = unix_now();
+
+        con.execute(
+            "INSERT INTO visited_dirs(path, visits, last_visit) VALUES (?, 1, ?)
+                ON CONFLICT(path) DO UPDATE SET
+                    visits = visits + 1, last_visit = excluded.last_visit;",
+            params![path.as_os_str().as_bytes(), now],
+        )
+        .unwrap_or_else(|e| {
+            error!("Error recording visit to {path:?}: {e}");
+            0
+        });
+
+        // Age old entries so the table doesn't grow forever and stale favourites fade out.
+        // The running total is kept in metadata so it doesn't need to be summed on every visit.
+        let total: i64 = con
+            .query_row(
+                "INSERT INTO metadata(key, value) VALUES ('total_visits', 1)
+                    ON CONFLICT(key) DO UPDATE SET value = value + 1
+                    RETURNING CAST(value AS INTEGER)",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap_or_default();
+
+        if total > MAX_TOTAL_VISITS {
+            debug!("Aging visited directories");
+            drop(con.execute_batch(
+                "UPDATE visited_dirs SET visits = visits * 9 / 10;
+                 DELETE FROM visited_dirs WHERE visits < 1;
+                 UPDATE metadata SET value = (SELECT COALESCE(SUM(visits), 0) FROM visited_dirs)
+                    WHERE key = 'total_visits';",
+            ));
+        }
+    }
+
+    fn recent_dirs(&self) -> Vec<(Arc<Path>, f64)> {
+        let con = &self.0;
+        let now = unix_now();
+
+        let mut stmt = match con.prepare("SELECT path, visits, last_visit FROM visited_dirs") {
+            Ok(stmt) => stmt,
+            Err(e) => {
+                error!("Error reading visited directories: {e}");
+                return Vec::new();
+            }
+        };
+
+        let rows = stmt.query_map([], |row| {
+            let path: &[u8] = row.get_ref(0)?.as_bytes()?;
+            let path: Arc<Path> = Path::new(OsStr::from_bytes(path)).into();
+            let visits: i64 = row.get(1)?;
+            let last_visit: i64 = row.get(2)?;
+
+            Ok((path, frecency(visits, now - last_visit)))
+        });
+
+        let mut dirs: Vec<_> = match rows.and_then(Iterator::collect) {
+            Ok(dirs) => dirs,
+            Err(e) => {
+                error!("Error reading visited directories: {e}");
+                return Vec::new();
+            }
+        };
+
+        // Checked here to keep it off the GUI thread, deleted directories are only aged out
+        dirs.retain(|(p, _)| p.is_dir());
+        dirs.sort_by(|a, b| b.1.total_cmp(&a.1));
+        dirs
+    }
+
+    fn add_command(&self, command: &str) {
+        let con = &self.0;
+
+        // Replacing moves a repeated command to the end
+        con.execute("INSERT OR REPLACE INTO command_history(command) VALUES (?);", [command])
+            .unwrap_or_else(|e| {
+                error!("Error recording command {command:?}: {e}");
+                0
+            });
+
+        drop(con.execute(
+            "DELETE FROM command_history WHERE id <= (SELECT MAX(id) FROM command_history) - ?;",
+            [MAX_COMMAND_HISTORY],
+        ));
+    }
+
+    fn command_history(&self) -> Vec<String> {
+        let con = &self.0;
+
+        let mut stmt = match con.prepare("SELECT command FROM command_history ORDER BY id") {
+            Ok(stmt) => stmt,
+            Err(e) => {
+                error!("Error reading command history: {e}");
+                return Vec::new();
+            }
+        };
+
+        let rows = stmt.query_map([], |row| row.get(0));
+
+        match rows.and_then(Iterator::collect) {
+            Ok(history) => history,
+            Err(e) => {
+                error!("Error reading command history: {e}");
+                Vec::new()
+            }
+        }
+    }
+
+    fn mark_running(&self) -> InstanceState {
+        self.try_mark_running().unwrap_or_else(|e| {
+            error!("Error writing running state: {e}");
+            InstanceState::default()
+        })
+    }
+
+    fn try_mark_running(&self) -> rusqlite::Result<InstanceState> {
+        let pid = std::process::id();
+        // Immediate so two instances starting together can't both become the primary
+        let tx = Transaction::new_unchecked(&self.0, TransactionBehavior::Immediate)?;
+
+        let instances: Vec<(u32, bool)> = tx
+            .prepare("SELECT pid, is_primary FROM running_instances")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+
+        let (alive, stale): (Vec<_>, Vec<_>) =
+            instances.into_iter().partition(|(p, _)| *p != pid && process_alive(*p));
+
+        // Secondary instances can outlive the primary, which doesn't make any of them primary
+        let primary = !alive.iter().any(|(_, was_primary)| *was_primary);
+        let mut crashed = false;
+
+        // Stale entries are kept until there's a new primary to ask about restoring
+        if primary {
+            for (p, was_primary) in stale {
+                crashed |= was_primary;
+                tx.execute("DELETE FROM running_instances WHERE pid = ?;", [p])?;
+            }
+        }
+
+        tx.execute(
+            "INSERT OR REPLACE INTO running_instances(pid, is_primary) VALUES (?, ?);",
+            params![pid, primary],
+        )?;
+        tx.commit()?;
+
+        Ok(InstanceState { primary, crashed })
+    }
+
+    fn mark_clean(&self) {
+        let con = &self.0;
+
+        con.execute("DELETE FROM running_instances WHERE pid = ?;", [std::process::id()])
+            .unwrap_or_else(|e| {
+                error!("Error writing running state: {e}");
+                0
+            });
+    }
+
+    fn session_names(&self) -> Vec<String> {
+        let con = &self.0;
+
+        let mut stmt = match con.prepare("SELECT name FROM sessions ORDER BY name") {
+            Ok(stmt) => stmt,
+            Err(e) => {
+                error!("Error reading saved sessions: {e}");
+                return Vec::new();
+            }
+        };
+
+        let rows = stmt.query_map([], |row| row.get(0));
+
+        match rows.and_then(Iterator::collect) {
+            Ok(names) => names,
+            Err(e) => {
+                error!("Error reading saved sessions: {e}");
+                Vec::new()
+            }
+        }
+    }
+
+    fn saved_searches(&self) -> Vec<SavedSearch> {
+        let con = &self.0;
+
+        let mut stmt = match con.prepare(
+            "SELECT name, root, query, max_depth, show_all FROM saved_searches ORDER BY name",
+        ) {
+            Ok(stmt) => stmt,
+            Err(e) => {
+                error!("Error reading saved searches: {e}");
+                return Vec::new();
+            }
+        };
+
+        let rows = stmt.query_map([], |row| {
+            let root: &[u8] = row.get_ref(1)?.as_bytes()?;
+            let root = Path::new(OsStr::from_bytes(root)).into();
+
+            // Searches saved before options were stored use whatever is configured
+            let options = match row.get::<_, Option<bool>>(4)? {
+                Some(show_all) => SearchOptions { max_depth: row.get(3)?, show_all },
+                None => SearchOptions::configured(),
+            };
+
+            Ok(SavedSearch { name: row.get(0)?, root, query: row.get(2)?, options })
+        });
+
+        match rows.and_then(Iterator::collect) {
+            Ok(searches) => searches,
+            Err(e) => {
+                error!("Error reading saved searches: {e}");
+                Vec::new()
+            }
+        }
+    }
+
+    fn save_search(&self, search: SavedSearch) {
+        let start = Instant::now();
+        let con = &self.0;
+
+        con.execute(
+            "INSERT OR REPLACE INTO saved_searches(name, root, query, max_depth, show_all)
+                VALUES (?, ?, ?, ?, ?);",
+            params![
+                search.name,
+                search.root.as_os_str().as_bytes(),
+                search.query,
+                search.options.max_depth,
+                search.options.show_all
+            ],
+        )
+        .unwrap_or_else(|e| {
+            error!("Error writing saved search {}: {e}", search.name);
+            0
+        });
+
+        trace!("Saved search {} in {:?}", search.name, start.elapsed());
+    }
+
+    fn delete_search(&self, name: &str) {
+        let start = Instant::now();
+        let con = &self.0;
+
+        drop(con.execute("DELETE FROM saved_searches WHERE name = ?", [name]));
+
+        trace!("Deleted saved search {name} in {:?}", start.elapsed());
+    }
 }
 
 
@@ -354,6 +803,32 @@ impl FromSql for SortDir {
     }
 }
 
+const MAX_TOTAL_VISITS: i64 = 10_000;
+const MAX_COMMAND_HISTORY: i64 = 1000;
+
+// A PID can be reused after a crash, which only means a crash goes unnoticed.
+fn process_alive(pid: u32) -> bool {
+    // Signal 0 only checks whether the process exists
+    let ret = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    ret == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64)
+}
+
+// Recent visits count for much more than old ones.
+fn frecency(visits: i64, age_seconds: i64) -> f64 {
+    let weight = match age_seconds {
+        ..3600 => 4.0,
+        ..86400 => 2.0,
+        ..604_800 => 0.5,
+        _ => 0.25,
+    };
+
+    visits as f64 * weight
+}
+
 fn get_version(con: &Connection) -> u32 {
     let r = con.query_row(
         "SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'metadata';",
@@ -437,4 +912,79 @@ ALTER TABLE sessions
     ADD COLUMN groups BLOB; -- nullable
 "#,
     );
+    update_to(
+        con,
+        4,
+        initial_version,
+        r#"
+CREATE TABLE saved_searches(
+    name TEXT NOT NULL,
+    root BLOB NOT NULL, -- possibly invalid UTF-8, like session paths
+    query TEXT NOT NULL,
+    PRIMARY KEY(name)
+);"#,
+    );
+    update_to(
+        con,
+        5,
+        initial_version,
+        r#"
+ALTER TABLE saved_searches
+    ADD COLUMN max_depth INTEGER; -- nullable, unlimited when null
+ALTER TABLE saved_searches
+    ADD COLUMN show_all INTEGER; -- nullable, only null for searches saved without options
+"#,
+    );
+    update_to(
+        con,
+        6,
+        initial_version,
+        r#"
+ALTER TABLE sessions
+    ADD COLUMN tabs BLOB; -- nullable
+"#,
+    );
+    update_to(
+        con,
+        7,
+        initial_version,
+        r#"
+DELETE FROM metadata WHERE key = 'running';
+CREATE TABLE running_instances(
+    pid INTEGER NOT NULL,
+    is_primary INTEGER NOT NULL,
+    PRIMARY KEY(pid)
+);"#,
+    );
+    update_to(
+        con,
+        8,
+        initial_version,
+        r#"
+CREATE TABLE visited_dirs(
+    path BLOB NOT NULL, -- possibly invalid UTF-8
+    visits INTEGER NOT NULL,
+    last_visit INTEGER NOT NULL, -- seconds since the unix epoch
+    PRIMARY KEY(path)
+);"#,
+    );
+    update_to(
+        con,
+        9,
+        initial_version,
+        r#"
+INSERT OR REPLACE INTO metadata(key, value)
+    SELECT 'total_visits', COALESCE(SUM(visits), 0) FROM visited_dirs;
+"#,
+    );
+    update_to(
+        con,
+        10,
+        initial_version,
+        r#"
+CREATE TABLE command_history(
+    id INTEGER PRIMARY KEY, -- increases with each use
+    command TEXT NOT NULL UNIQUE
+);"#,
+    );
 }
@@ -1,31 +1,48 @@
 use std::collections::{VecDeque, hash_map};
 use std::ffi::OsString;
-use std::path::{Path, PathBuf};
+use std::path::Path;
 use std::rc::Rc;
-use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
 use ahash::AHashMap;
 use dirs::home_dir;
-use gtk::gdk::{Key, ModifierType};
+use gtk::gdk::{self, Key, ModifierType};
 use gtk::glib::{self, Propagation};
 use gtk::pango::{EllipsizeMode, WrapMode};
 use gtk::prelude::*;
 use gtk::subclass::prelude::ObjectSubclassIsExt;
 use gtk::{Orientation, Widget, Window};
+use serde_json::json;
+use tokio::sync::oneshot;
 
+use self::commands::{Command, FocusTarget};
 use super::properties::dialog::PropDialog;
+use super::session::LAST_SESSION;
 use super::tabs::id::TabId;
-use super::{ActionTarget, Gui, label_attributes};
+use super::{ActionTarget, Gui, label_attributes, load_css};
 use crate::closing;
-use crate::com::{DisplayMode, EntryObject, ManagerAction, SortDir, SortMode};
-use crate::config::{CONFIG, OPTIONS, Shortcut};
+use crate::com::{EntryObject, ExecContext, ManagerAction, SelectionVia};
+use crate::config::{self, CONFIG, OPTIONS};
+use crate::database::SavedSearch;
 use crate::gui::operations::Kind;
-use crate::gui::tabs::list::TabPosition;
-use crate::gui::{gui_run, show_warning};
+use crate::gui::tabs::list::{PaneDirection, TabPosition};
+use crate::gui::{gui_run, show_error, show_warning};
 
+mod alias;
+mod check;
+mod command_line;
+mod commands;
 mod help;
+mod palette;
+mod picker;
+mod prompt;
+
+pub use self::check::check_config;
+pub(super) use self::command_line::CommandLine;
+
+// Scripts probably don't want or need the full list
+const RECENT_DIRS_ENV_LIMIT: usize = 50;
 
 #[derive(Debug, Default)]
 pub(super) struct OpenDialogs {
@@ -52,11 +69,14 @@ impl Gui {
         let key = gtk::EventControllerKey::new();
 
         let g = self.clone();
-        key.connect_key_pressed(move |_e, a, _b, c| {
-            if let Some(s) = g.shortcut_from_key(a, c) {
-                g.run_command_active(s);
+        key.connect_key_pressed(move |e, a, _b, c| {
+            // Pressing a modifier on its own shouldn't interrupt a sequence
+            let event = e.current_event().and_then(|ev| ev.downcast::<gdk::KeyEvent>().ok());
+            if event.is_some_and(|ev| ev.is_modifier()) {
+                return Propagation::Proceed;
             }
-            Propagation::Proceed
+
+            g.handle_key(a, c)
         });
 
         self.window.add_controller(key);
@@ -67,7 +87,9 @@ impl Gui {
             self.setup_idle_unload(Duration::from_secs(unload.get()));
         }
 
+        self.setup_command_line();
         self.setup_bookmarks();
+        self.warn_config_problems();
     }
 
     fn setup_idle_unload(self: &Rc<Self>, idle: Duration) {
@@ -145,27 +167,35 @@ impl Gui {
         self.window.add_controller(focus);
     }
 
+    // Also called to rebuild the left bar when saved searches change.
     fn setup_bookmarks(self: &Rc<Self>) {
         let bookmarks = if OPTIONS.chooser_mode.is_some() {
             CONFIG.chooser_bookmarks.as_ref().unwrap_or(&CONFIG.bookmarks)
         } else {
             &CONFIG.bookmarks
         };
-        if bookmarks.is_empty() {
+        let searches = self.saved_searches.borrow();
+
+        let container = &self.window.imp().bookmarks;
+        while let Some(child) = container.first_child() {
+            container.remove(&child);
+        }
+
+        if bookmarks.is_empty() && searches.is_empty() {
             if OPTIONS.chooser_mode.is_some() {
                 self.window.imp().left_bar.set_visible(false);
             }
             return;
         }
 
-        let container = &self.window.imp().bookmarks;
-
-        let header = gtk::Label::builder()
-            .label("Bookmarks")
-            .css_classes(["left-header"])
-            .xalign(0.0)
-            .build();
-        container.append(&header);
+        if !bookmarks.is_empty() {
+            let header = gtk::Label::builder()
+                .label("Bookmarks")
+                .css_classes(["left-header"])
+                .xalign(0.0)
+                .build();
+            container.append(&header);
+        }
 
         for book in bookmarks {
             let label = gtk::Label::builder()
@@ -183,7 +213,40 @@ impl Gui {
             click.connect_pressed(move |gc, _n, _x, _y| {
                 let command = gc.widget().unwrap().tooltip_text().unwrap();
                 info!("Running command from clicked bookmark: {command}");
-                g.run_command_active(&command);
+                g.run_action_active(&command);
+            });
+
+            label.add_controller(click);
+
+            container.append(&label);
+        }
+
+        if !searches.is_empty() {
+            let header = gtk::Label::builder()
+                .label("Searches")
+                .css_classes(["left-header"])
+                .xalign(0.0)
+                .build();
+            container.append(&header);
+        }
+
+        for search in searches.iter() {
+            let label = gtk::Label::builder()
+                .label(&search.name)
+                .tooltip_text(format!("{}: {}", search.root.display(), search.query))
+                .max_width_chars(1)
+                .ellipsize(EllipsizeMode::End)
+                .css_classes(["bookmark"])
+                .xalign(0.0)
+                .build();
+
+            let click = gtk::GestureClick::new();
+            click.set_button(1);
+            let g = self.clone();
+            let name = search.name.clone();
+            click.connect_pressed(move |_gc, _n, _x, _y| {
+                info!("Opening clicked saved search: {name}");
+                g.open_saved_search(&name);
             });
 
             label.add_controller(click);
@@ -199,6 +262,52 @@ impl Gui {
         container.append(&header);
     }
 
+    fn save_search(self: &Rc<Self>, target: ActionTarget, name: &str) {
+        let Some((root, query, options)) = self.tabs.borrow().get_search(target) else {
+            return show_warning(format!("Can't save search {name:?} from a tab not searching"));
+        };
+
+        let search = SavedSearch { name: name.to_string(), root, query, options };
+
+        info!("Saving search {search:?}");
+        self.database.save_search(search.clone());
+
+        let mut searches = self.saved_searches.borrow_mut();
+        match searches.binary_search_by(|s| s.name.as_str().cmp(name)) {
+            Ok(i) => searches[i] = search,
+            Err(i) => searches.insert(i, search),
+        }
+        drop(searches);
+
+        self.setup_bookmarks();
+    }
+
+    fn open_saved_search(self: &Rc<Self>, name: &str) {
+        let searches = self.saved_searches.borrow();
+        let Some(search) = searches.iter().find(|s| s.name == name) else {
+            return show_warning(format!("No saved search named {name:?}"));
+        };
+
+        let (root, query, options) = (search.root.clone(), search.query.clone(), search.options);
+        drop(searches);
+
+        self.tabs.borrow_mut().open_search_tab(root, query, options);
+    }
+
+    fn delete_saved_search(self: &Rc<Self>, name: &str) {
+        let mut searches = self.saved_searches.borrow_mut();
+        let Ok(i) = searches.binary_search_by(|s| s.name.as_str().cmp(name)) else {
+            return show_warning(format!("No saved search named {name:?}"));
+        };
+
+        info!("Deleting saved search {name}");
+        searches.remove(i);
+        drop(searches);
+
+        self.database.delete_search(name.to_string());
+        self.setup_bookmarks();
+    }
+
     pub(super) fn rename_dialog(self: &Rc<Self>, tab: TabId, eo: EntryObject) {
         let path = eo.get().abs_path.clone();
 
@@ -354,8 +463,9 @@ impl Gui {
         let key = gtk::EventControllerKey::new();
         let g = self.clone();
         key.connect_key_pressed(move |e, key, _b, mods| {
+            let chord = (mods & !ModifierType::LOCK_MASK, key.to_upper());
             if (mods.is_empty() && key == Key::Escape)
-                || g.shortcut_from_key(key, mods).is_some_and(|s| s == "Quit")
+                || g.shortcuts.borrow().global(chord) == Some("Quit")
             {
                 e.widget()
                     .and_downcast::<gtk::Window>()
@@ -368,39 +478,6 @@ impl Gui {
         w.add_controller(key);
     }
 
-    fn shortcut_from_key(self: &Rc<Self>, k: Key, mods: ModifierType) -> Option<&'static str> {
-        let mods = mods & !ModifierType::LOCK_MASK;
-        let upper = k.to_upper();
-
-        self.shortcuts.get(&mods)?.get(&upper).map(|v| &**v)
-    }
-
-    pub(super) fn parse_shortcuts() -> AHashMap<ModifierType, AHashMap<Key, &'static str>> {
-        let mut shortcuts = AHashMap::new();
-
-        let mut handle = |s: &'static Shortcut| {
-            let modifiers = parse_modifiers(&s.modifiers);
-
-            let inner = match shortcuts.entry(modifiers) {
-                hash_map::Entry::Occupied(inner) => inner.into_mut(),
-                hash_map::Entry::Vacant(vacant) => vacant.insert(AHashMap::new()),
-            };
-
-            let k = Key::from_name(&s.key)
-                .unwrap_or_else(|| panic!("Could not decode Key: {}", &s.key));
-            inner.insert(k, &*s.action);
-        };
-
-        CONFIG.shortcuts.iter().for_each(&mut handle);
-
-
-        if OPTIONS.chooser_mode.is_some() {
-            CONFIG.chooser_shortcuts.iter().for_each(&mut handle);
-        }
-
-        shortcuts
-    }
-
     pub(super) fn parse_mouse_actions() -> AHashMap<ModifierType, AHashMap<u32, &'static str>> {
         let mut actions = AHashMap::new();
 
@@ -417,9 +494,87 @@ impl Gui {
         actions
     }
 
-    pub(super) fn run_command_active(self: &Rc<Self>, cmd: &str) {
+    // Everything built from the config at startup is rebuilt, anything else reads CONFIG directly.
+    pub(super) fn reload_config(self: &Rc<Self>) {
+        let old = match config::reload_config() {
+            Ok(old) => old,
+            Err(e) => return show_error(format!("Failed to reload config: {e}")),
+        };
+
+        self.batch_warnings(|| {
+            self.reload_keymap();
+            self.mouse_actions.replace(Self::parse_mouse_actions());
+            self.setup_bookmarks();
+            self.menu.get().unwrap().rebuild_menu(self);
+            load_css(&self.window, &self.css);
+            self.warn_config_problems();
+
+            let restart = old.restart_required(&CONFIG);
+            if restart.is_empty() {
+                info!("Reloaded config");
+            } else {
+                show_warning(format!(
+                    "Reloaded config, restart for changes to {} to take effect",
+                    restart.join(", ")
+                ));
+            }
+        });
+    }
+
+    pub(super) fn run_action_active(self: &Rc<Self>, cmd: &str) {
         let target = self.tabs.borrow().active_action_target();
-        self.run_command(target, cmd)
+        self.run_action(target, cmd)
+    }
+
+    // Runs the command and collects any warnings or errors it showed.
+    // Errors from asynchronous follow-up work, like file operations, are not captured.
+    pub(super) fn run_command_checked(
+        self: &Rc<Self>,
+        target: ActionTarget,
+        cmd: &str,
+    ) -> Result<(), String> {
+        self.capture_errors(|| self.run_command(target, cmd))
+    }
+
+    fn capture_errors(&self, f: impl FnOnce()) -> Result<(), String> {
+        let outer = self.command_errors.replace(Some(Vec::new()));
+        f();
+        let errors = self.command_errors.replace(outer).unwrap_or_default();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+
+    // Runs a command from an interactive script, which may need to wait on the user to respond.
+    pub(super) fn interactive_action(
+        self: &Rc<Self>,
+        line: &str,
+        target: ActionTarget,
+        respond: oneshot::Sender<String>,
+    ) {
+        let (cmd, arg) = line.trim_start().split_once(' ').unwrap_or((line.trim(), ""));
+        let arg = arg.trim();
+
+        match cmd {
+            "Prompt" => self.prompt_dialog(arg, respond),
+            "Confirm" => self.confirm_dialog(arg, respond),
+            "Choose" => {
+                let mut words = prompt::split_quoted(arg).into_iter();
+                let title = words.next().unwrap_or_default();
+                let options: Vec<_> = words.collect();
+
+                if options.is_empty() {
+                    let error = "Choose requires a title and at least one option";
+                    return drop(respond.send(json!({ "ok": false, "error": error }).to_string()));
+                }
+
+                self.choose_dialog(&title, options, respond);
+            }
+            _ => drop(respond.send(self.handle_request(line, target).to_string())),
+        }
     }
 
     pub(super) fn run_mouse_command(
@@ -430,174 +585,366 @@ impl Gui {
     ) {
         let mods = mods & !ModifierType::LOCK_MASK;
 
-        let Some(cmd) = self.mouse_actions.get(&mods).and_then(|m| m.get(&button)) else {
+        let Some(cmd) = self.mouse_actions.borrow().get(&mods).and_then(|m| m.get(&button)).copied()
+        else {
             return;
         };
 
-        self.run_command(target, cmd);
+        self.run_action(target, cmd);
     }
 
-    pub(super) fn run_command(self: &Rc<Self>, target: ActionTarget, cmd: &str) {
-        // Do not trim the end of cmd because files and directories can end in spaces
-        let cmd = cmd.trim_start();
-
-        debug!("Running command {cmd} in {target:?}");
-
-        // This may not be worth the headache, but it saves a fair bit of boilerplate
-        let mut tabs = self.tabs.borrow_mut();
+    // Runs a single command or a chain of commands separated by semicolons, expanding aliases.
+    // A chain stops at the first step that fails.
+    // Only for configured or typed actions, output from scripts and IPC requests is always a
+    // single command, since paths and search queries can contain semicolons.
+    pub(super) fn run_action(self: &Rc<Self>, target: ActionTarget, cmd: &str) {
+        let steps = match alias::expand(cmd) {
+            Ok(steps) => steps,
+            Err(e) => {
+                warn!("{e}");
+                return self.warning(e);
+            }
+        };
 
+        if let [step] = &steps[..] {
+            return self.run_command(target, step);
+        }
 
-        if let Some((cmd, arg)) = cmd.split_once(' ') {
-            let arg = arg.trim_start();
-
-            let _ = match cmd {
-                "Display" => match DisplayMode::from_str(arg) {
-                    Ok(m) => return tabs.display_mode(target, m),
-                    Err(_e) => true,
-                },
-                "SortBy" => match SortMode::from_str(arg) {
-                    Ok(m) => return tabs.sort_mode(target, m),
-                    Err(_e) => true,
-                },
-                "SortDir" => match SortDir::from_str(arg) {
-                    Ok(d) => return tabs.sort_direction(target, d),
-                    Err(_e) => true,
-                },
-
-                "Navigate" => return tabs.navigate(target, Path::new(arg)),
-                "JumpTo" => return tabs.jump(target, Path::new(arg)),
-                "NewTab" => return tabs.open_tab(Path::new(arg), TabPosition::After(target), true),
-                "NewBackgroundTab" => {
-                    return tabs.open_tab(Path::new(arg), TabPosition::After(target), false);
-                }
+        let mut target = target;
+        for (i, step) in steps.iter().enumerate() {
+            let before = self.tabs.borrow().tab_ids();
 
-                "Split" => match arg {
-                    "horizontal" => {
-                        return tabs.visible_split(target, Orientation::Horizontal, None);
-                    }
-                    "vertical" => {
-                        return tabs.visible_split(target, Orientation::Vertical, None);
-                    }
-                    _ => true,
-                },
+            if let Err(e) = self.capture_errors(|| self.run_command(target, step)) {
+                let e = format!("Step {} of {} ({step:?}) failed: {e}", i + 1, steps.len());
+                warn!("{e}");
+                return self.warning(e);
+            }
 
-                "Search" => return tabs.search(target, arg),
+            // Later steps follow a newly opened tab, so "NewTab; SortBy mtime" sorts the new tab
+            if let Some(id) = self.tabs.borrow().opened_tab(&before) {
+                target = ActionTarget::Tab(id);
+            }
+        }
+    }
 
-                "SaveSession" => {
-                    if let Some(session) = tabs.get_session() {
-                        self.database.save_session(arg.to_owned(), session);
-                    } else {
-                        show_warning("No tabs open to save as session");
-                    }
-                    return;
-                }
-                "LoadSession" => {
-                    if let Some(session) = self.database.load_session(arg.to_string()) {
-                        tabs.load_session(session);
-                    } else {
-                        show_warning(format!("No session named \"{arg}\" found"));
-                    }
-                    return;
-                }
-                "DeleteSession" => {
-                    return self.database.delete_session(arg.to_string());
-                }
+    pub(super) fn run_command(self: &Rc<Self>, target: ActionTarget, cmd: &str) {
+        debug!("Running command {} in {target:?}", cmd.trim_start());
 
-                "Execute" => {
-                    drop(tabs);
-                    return self.send_manager(ManagerAction::Execute(
-                        PathBuf::from(arg).into(),
-                        self.get_env(),
-                    ));
-                }
-                "Script" => {
-                    drop(tabs);
-                    return self.send_manager(ManagerAction::Script(
-                        PathBuf::from(arg).into(),
-                        target,
-                        self.get_env(),
-                    ));
-                }
+        let (spec, command) = match Command::parse(cmd) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                warn!("{e}");
+                return self.warning(e);
+            }
+        };
 
-                _ => true,
-            };
+        if !spec.chooser && OPTIONS.chooser_mode.is_some() {
+            let e = format!("{} is not available in the file chooser", spec.name);
+            warn!("{e}");
+            return self.warning(e);
         }
 
-        let _ = match cmd {
-            "Quit" => {
+        // This may not be worth the headache, but it saves a fair bit of boilerplate
+        let mut tabs = self.tabs.borrow_mut();
+
+        match command {
+            Command::Quit => {
                 if OPTIONS.chooser_mode.is_some() {
                     println!("cancelled");
                 }
                 closing::close();
-                return self.window.close();
+                self.window.close();
             }
-            "Help" => return self.help_dialog(),
-            "Activate" => return tabs.activate(target),
-            "OpenDefault" => return tabs.open_default(target),
-            "OpenWith" => return tabs.open_with(target),
-
-            "Copy" => return tabs.copy(target),
-            "Cut" => return tabs.cut(target),
-            "Paste" => return tabs.paste(target),
-
-            "Cancel" => {
+            Command::Help => self.help_dialog(),
+            Command::CommandPalette => {
                 drop(tabs);
-                return self.cancel_operations();
+                self.command_palette();
             }
-            "Undo" => {
+            Command::CommandLine(text) => {
+                drop(tabs);
+                self.open_command_line(target, text);
+            }
+            Command::Mode(name) => self.set_mode(Some(name)),
+            Command::ExitMode => self.set_mode(None),
+            Command::RunningScripts => self.scripts_panel(),
+            Command::KillScripts => self.kill_scripts(),
+            Command::Activate => tabs.activate(target),
+            Command::OpenDefault => tabs.open_default(target),
+            Command::OpenWith => tabs.open_with(target),
+
+            Command::Copy => tabs.copy(target),
+            Command::Cut => tabs.cut(target),
+            Command::Paste => tabs.paste(target),
+
+            Command::Cancel => {
+                drop(tabs);
+                self.cancel_operations();
+            }
+            Command::Undo => {
                 drop(tabs);
                 // TODO -- should this be tied to a specific tab? or directory?
-                return self.undo_operation();
+                self.undo_operation();
             }
 
-            "Home" => {
-                return tabs.navigate(target, &home_dir().unwrap_or_default());
+            Command::Home => tabs.navigate(target, &home_dir().unwrap_or_default()),
+            Command::Navigate(path) => tabs.navigate(target, path),
+            Command::JumpTo(path) => tabs.jump(target, path),
+
+            Command::NewTab(Some(path)) => tabs.open_tab(path, TabPosition::After(target), true),
+            Command::NewTab(None) => tabs.new_tab(target, true),
+            Command::NewBackgroundTab(Some(path)) => {
+                tabs.open_tab(path, TabPosition::After(target), false);
             }
+            Command::NewBackgroundTab(None) => tabs.new_tab(target, false),
+            Command::ReopenTab => tabs.reopen(),
 
-            "NewTab" => return tabs.new_tab(target, true),
-            "NewBackgroundTab" => return tabs.new_tab(target, false),
-            "ReopenTab" => return tabs.reopen(),
+            Command::Refresh => tabs.refresh(target),
+            Command::RefreshAll => tabs.refresh_all(),
+            Command::ReloadActions => self.menu.get().unwrap().rebuild_menu(self),
+            Command::ReloadConfig => {
+                drop(tabs);
+                self.reload_config();
+            }
 
-            "Refresh" => return tabs.refresh(target),
-            "RefreshAll" => return tabs.refresh_all(),
-            "ReloadActions" => return self.menu.get().unwrap().rebuild_menu(self),
+            Command::Display(mode) => tabs.display_mode(target, mode),
+            Command::SortBy(mode) => tabs.sort_mode(target, mode),
+            Command::SortDir(dir) => tabs.sort_direction(target, dir),
+
+            Command::Split(orient) => tabs.visible_split(target, orient, None),
+            Command::MovePane(dir) => {
+                let (orient, towards_start) = match dir {
+                    PaneDirection::Left => (Orientation::Horizontal, true),
+                    PaneDirection::Right => (Orientation::Horizontal, false),
+                    PaneDirection::Up => (Orientation::Vertical, true),
+                    PaneDirection::Down => (Orientation::Vertical, false),
+                };
+                tabs.move_pane(target, orient, towards_start);
+            }
+            Command::SwapPanes => tabs.swap_panes(target),
+            Command::RotateSplit => tabs.rotate_split(target),
+            Command::FocusPane(focus) => match focus {
+                FocusTarget::Next => tabs.cycle_pane(target, true),
+                FocusTarget::Prev => tabs.cycle_pane(target, false),
+                FocusTarget::Left => tabs.focus_pane(target, PaneDirection::Left),
+                FocusTarget::Right => tabs.focus_pane(target, PaneDirection::Right),
+                FocusTarget::Up => tabs.focus_pane(target, PaneDirection::Up),
+                FocusTarget::Down => tabs.focus_pane(target, PaneDirection::Down),
+            },
+            Command::ResizePane(dir, amount) => tabs.resize_pane(target, dir, amount),
+            Command::EqualizePanes => tabs.equalize_panes(target),
+            Command::CopyToOtherPane => {
+                tabs.operate_on_other_pane(target, "CopyToOtherPane", Kind::Copy);
+            }
+            Command::MoveToOtherPane => {
+                tabs.operate_on_other_pane(target, "MoveToOtherPane", Kind::Move);
+            }
+            Command::LinkToOtherPane => {
+                tabs.operate_on_other_pane(target, "LinkToOtherPane", Kind::Link);
+            }
+            Command::SyncOtherPane => tabs.sync_other_pane(target),
+
+            Command::CloseTab => tabs.close_tab(target),
+            Command::ClosePane => tabs.close_pane(target),
+            Command::HidePanes => tabs.hide_all_visible(target),
+            Command::CloseTabNoReplacement => tabs.close_tab_no_replacement(target),
+
+            Command::Forward => tabs.forward(target),
+            Command::Back => tabs.back(target),
+            Command::Parent => tabs.parent(target),
+            Command::Child => tabs.child(target),
+            Command::BackOrParent => tabs.back_or_parent(target),
+
+            Command::Trash => tabs.trash(target),
+            Command::Delete => tabs.active_delete(target),
+
+            Command::Rename => tabs.rename(target),
+            Command::Properties => tabs.properties(target),
+
+            Command::FocusLocation => tabs.focus_location_bar(target),
+            Command::Unselect => tabs.unselect(target),
+            Command::SelectAll => tabs.select_all(target),
+            Command::InvertSelection => tabs.invert_selection(target),
+            Command::SelectSameType => tabs.select_same_type(target),
+            Command::Select(pattern, mode) => tabs.select_pattern(target, &pattern, mode),
+            Command::SelectPaths(paths) => tabs.select_paths(target, paths),
+
+            Command::NewFolder => tabs.create(target, true),
+            Command::NewFile => tabs.create(target, false),
+
+            Command::Search(query) => tabs.search(target, query),
+            Command::JumpRecent => {
+                drop(tabs);
+                self.jump_recent(target);
+            }
 
-            "CloseTab" => return tabs.close_tab(target),
-            "ClosePane" => return tabs.close_pane(target),
-            "HidePanes" => return tabs.hide_all_visible(target),
-            "CloseActive" | "CloseTabNoReplacement" => {
-                return tabs.close_tab_no_replacement(target);
+            Command::SaveSession(name) | Command::DeleteSession(name) if name == LAST_SESSION => {
+                show_warning(format!("{LAST_SESSION} is reserved for the automatic session"));
             }
+            Command::SaveSession(name) => {
+                if let Some(session) = tabs.get_session() {
+                    self.database.save_session(name.to_owned(), session);
+                } else {
+                    show_warning("No tabs open to save as session");
+                }
+            }
+            Command::LoadSession(name) => {
+                if let Some(session) = self.database.load_session(name.to_string()) {
+                    tabs.load_session(session);
+                } else {
+                    show_warning(format!("No session named \"{name}\" found"));
+                }
+            }
+            Command::DeleteSession(name) => self.database.delete_session(name.to_string()),
 
-            "Forward" => return tabs.forward(target),
-            "Back" => return tabs.back(target),
-            "Parent" => return tabs.parent(target),
-            "Child" => return tabs.child(target),
-            "BackOrParent" => return tabs.back_or_parent(target),
+            Command::SaveSearch(name) => {
+                drop(tabs);
+                self.save_search(target, name);
+            }
+            Command::OpenSearch(name) => {
+                drop(tabs);
+                self.open_saved_search(name);
+            }
+            Command::DeleteSearch(name) => {
+                drop(tabs);
+                self.delete_saved_search(name);
+            }
 
-            "Trash" => return tabs.trash(target),
-            "Delete" => return tabs.active_delete(target),
+            Command::Execute(exe) => {
+                drop(tabs);
+                self.send_manager(ManagerAction::Execute(
+                    exe.into(),
+                    self.exec_context(target, SelectionVia::Env),
+                ));
+            }
+            Command::Script(exe) => {
+                drop(tabs);
+                self.send_manager(ManagerAction::Script(
+                    exe.into(),
+                    target,
+                    self.exec_context(target, SelectionVia::Env),
+                ));
+            }
+            Command::InteractiveScript(exe) => {
+                drop(tabs);
+                self.send_manager(ManagerAction::InteractiveScript(
+                    exe.into(),
+                    target,
+                    self.exec_context(target, SelectionVia::Env),
+                ));
+            }
+        }
+    }
 
-            "Rename" => return tabs.rename(target),
-            "Properties" => return tabs.properties(target),
+    pub(super) fn visit_dir(&self, path: Arc<Path>) {
+        // Browsing to pick files shouldn't change where JumpRecent goes
+        if OPTIONS.chooser_mode.is_some() {
+            return;
+        }
 
-            "FocusLocation" => return tabs.focus_location_bar(target),
-            "Unselect" => return tabs.unselect(target),
+        self.database.visit(path);
 
-            "NewFolder" => return tabs.create(target, true),
-            "NewFile" => return tabs.create(target, false),
+        // Restoring a session opens many tabs at once, so this only refreshes once they're open
+        if !self.recent_dirs_stale.replace(true) {
+            glib::idle_add_local_once(|| gui_run(|g| g.refresh_recent_dirs()));
+        }
+    }
 
-            "Search" => return tabs.search(target, ""),
-            _ => true,
-        };
+    // Ranking every visited directory is slow, so readers use the last result and it's refreshed in
+    // the background.
+    pub(super) fn refresh_recent_dirs(&self) {
+        self.recent_dirs_stale.set(false);
+        let recv = self.database.recent_dirs();
+        glib::spawn_future_local(async move {
+            let Ok(dirs) = recv.await else {
+                return;
+            };
+            gui_run(|g| *g.recent_dirs.borrow_mut() = dirs.into());
+        });
+    }
+
+    fn jump_recent(self: &Rc<Self>, target: ActionTarget) {
+        let dirs: Vec<Arc<Path>> = self
+            .recent_dirs
+            .borrow()
+            .iter()
+            .map(|(p, _score)| p.clone())
+            .collect();
+
+        if dirs.is_empty() {
+            return show_warning("No recently visited directories");
+        }
 
-        let e = format!("Unrecognized command {cmd:?}");
-        warn!("{e}");
-        self.warning(&e);
+        let choices = dirs.iter().map(|p| p.to_string_lossy().into_owned()).collect();
+
+        let g = self.clone();
+        self.fuzzy_picker("Recent Directories", choices, move |i| {
+            g.tabs.borrow_mut().navigate(target, &dirs[i]);
+        });
     }
 
     pub(super) fn get_env(&self) -> Vec<(String, OsString)> {
-        self.tabs.borrow().get_env()
+        let mut env = self.tabs.borrow().get_env();
+
+        env.push(("AWFM_PID".to_owned(), std::process::id().to_string().into()));
+
+        if let Some(server) = &*self.ipc.borrow() {
+            env.push(("AWFM_SOCKET".to_owned(), server.path.clone().into()));
+        }
+
+        #[cfg(feature = "x11")]
+        if let Some(surface) = self.window.surface().and_downcast::<gdk4_x11::X11Surface>() {
+            env.push(("AWFM_WINDOW".to_owned(), surface.xid().to_string().into()));
+        }
+
+        if OPTIONS.chooser_mode.is_none() {
+            let mut recent = OsString::new();
+            for (i, (path, _score)) in
+                self.recent_dirs.borrow().iter().take(RECENT_DIRS_ENV_LIMIT).enumerate()
+            {
+                if i > 0 {
+                    recent.push("\n");
+                }
+                recent.push(path.as_os_str());
+            }
+            env.push(("AWFM_RECENT_DIRS".to_owned(), recent));
+        }
+
+        env
+    }
+
+    pub(super) fn exec_context(
+        &self,
+        target: ActionTarget,
+        selection_via: SelectionVia,
+    ) -> ExecContext {
+        let mut env = self.get_env();
+
+        let cwd = if CONFIG.run_in_tab_directory {
+            self.tabs.borrow().target_dir(target)
+        } else {
+            None
+        };
+
+        let selection = if selection_via == SelectionVia::Env {
+            Vec::new()
+        } else {
+            // Large selections can exceed the limits on the size of the environment
+            env.retain(|(k, _)| k != "AWFM_SELECTION");
+            self.tabs.borrow().active_selection()
+        };
+
+        // Nobody would see it otherwise
+        let capture_output = self.scripts.borrow().panel_open();
+
+        ExecContext {
+            env,
+            cwd,
+            selection_via,
+            selection,
+            terminal: false,
+            timeout: None,
+            capture_output,
+        }
     }
 }
 
@@ -628,7 +975,7 @@ pub(super) fn wrap_in_box_with_close_button(
     action_box
 }
 
-fn parse_modifiers(modifiers: &Option<String>) -> ModifierType {
+pub(super) fn parse_modifiers(modifiers: &Option<String>) -> ModifierType {
     let mut mods: ModifierType = ModifierType::empty();
     if let Some(m) = modifiers {
         let m = m.to_lowercase();
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use super::prompt::split_quoted;
+use crate::config::CONFIG;
+
+// Splits a command on semicolons, unless they're escaped as "\;".
+// Only whitespace before a separator is trimmed, since paths can end in spaces.
+pub(super) fn split_chain(cmd: &str) -> Vec<String> {
+    let mut steps = Vec::new();
+    let mut current = String::new();
+
+    let mut chars = cmd.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&';') => {
+                current.push(';');
+                chars.next();
+            }
+            ';' => {
+                steps.push(current.trim().to_string());
+                current.clear();
+            }
+            c => current.push(c),
+        }
+    }
+
+    steps.push(current.trim_start().to_string());
+    steps.retain(|s| !s.is_empty());
+    steps
+}
+
+// Splits cmd into steps and recursively expands any aliases.
+pub(super) fn expand(cmd: &str) -> Result<Vec<String>, String> {
+    expand_with(&CONFIG.aliases, cmd, &mut Vec::new())
+}
+
+// An alias isn't expanded inside itself, so an alias can wrap a command of the same name.
+fn expand_with(
+    aliases: &HashMap<String, String>,
+    cmd: &str,
+    expanding: &mut Vec<String>,
+) -> Result<Vec<String>, String> {
+    let mut expanded = Vec::new();
+
+    for step in split_chain(cmd) {
+        let (name, args) = step.split_once(' ').unwrap_or((step.as_str(), ""));
+
+        let Some(template) = aliases.get(name) else {
+            expanded.push(step);
+            continue;
+        };
+
+        if expanding.iter().any(|a| a == name) {
+            expanded.push(step);
+            continue;
+        }
+
+        expanding.push(name.to_string());
+        for inner_step in split_chain(template) {
+            let substituted = substitute_args(name, &inner_step, args.trim())?;
+            expanded.extend(expand_with(aliases, &substituted, expanding)?);
+        }
+        expanding.pop();
+    }
+
+    Ok(expanded)
+}
+
+// Replaces $1 through $9 with arguments split like Choose options, so "a b" is one argument,
+// and $@ with all of them exactly as written. $$ is a literal $.
+fn substitute_args(alias: &str, template: &str, args: &str) -> Result<String, String> {
+    let words = split_quoted(args);
+    let mut out = String::with_capacity(template.len() + args.len());
+
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            out.push(c);
+            continue;
+        }
+
+        match chars.peek().copied() {
+            Some('$') => out.push('$'),
+            Some('@') => out.push_str(args),
+            Some(d @ '1'..='9') => {
+                let n = d as usize - '0' as usize;
+                let Some(word) = words.get(n - 1) else {
+                    return Err(format!(
+                        "Alias {alias} needs at least {n} argument(s), got {}",
+                        words.len()
+                    ));
+                };
+                out.push_str(word);
+            }
+            _ => {
+                out.push('$');
+                continue;
+            }
+        }
+        chars.next();
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{expand_with, split_chain, substitute_args};
+
+    fn expand(aliases: &[(&str, &str)], cmd: &str) -> Result<Vec<String>, String> {
+        let aliases: HashMap<_, _> =
+            aliases.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        expand_with(&aliases, cmd, &mut Vec::new())
+    }
+
+    fn substitute(template: &str, args: &str) -> String {
+        substitute_args("Alias", template, args).unwrap()
+    }
+
+    #[test]
+    fn chains() {
+        assert_eq!(split_chain("Home"), ["Home"]);
+        assert_eq!(split_chain("NewTab /tmp; Display columns"), ["NewTab /tmp", "Display columns"]);
+        assert_eq!(split_chain(" Home ;; Refresh;"), ["Home", "Refresh"]);
+        assert!(split_chain(" ; ").is_empty());
+    }
+
+    #[test]
+    fn escaped_semicolons() {
+        assert_eq!(split_chain(r"Navigate /a\;b; Home"), ["Navigate /a;b", "Home"]);
+        assert_eq!(split_chain(r"Search a\\b"), [r"Search a\\b"]);
+    }
+
+    #[test]
+    fn trailing_spaces_in_last_step() {
+        assert_eq!(split_chain("Navigate /tmp/dir "), ["Navigate /tmp/dir "]);
+    }
+
+    #[test]
+    fn arguments() {
+        assert_eq!(
+            substitute("Split $1; Navigate $2", "vertical /tmp"),
+            "Split vertical; Navigate /tmp"
+        );
+        assert_eq!(substitute("Navigate $1", r#""/tmp/a b""#), "Navigate /tmp/a b");
+        assert_eq!(substitute("Navigate $1", r"/tmp/a\ b"), "Navigate /tmp/a b");
+        assert_eq!(substitute("Search $@", "a  b"), "Search a  b");
+    }
+
+    #[test]
+    fn literal_dollars() {
+        assert_eq!(substitute("Execute $$1", "x"), "Execute $1");
+        assert_eq!(substitute("Home $x $", ""), "Home $x $");
+    }
+
+    #[test]
+    fn missing_arguments() {
+        let e = substitute_args("Alias", "Navigate $2", "one").unwrap_err();
+        assert_eq!(e, "Alias Alias needs at least 2 argument(s), got 1");
+    }
+
+    #[test]
+    fn expansion() {
+        let aliases = [("Down", "NewTab /d; SortBy mtime"), ("Go", "Navigate $1; Down")];
+
+        assert_eq!(expand(&aliases, "Home").unwrap(), ["Home"]);
+        assert_eq!(expand(&aliases, "Down; Home").unwrap(), ["NewTab /d", "SortBy mtime", "Home"]);
+        let expected = ["Navigate /x", "NewTab /d", "SortBy mtime"];
+        assert_eq!(expand(&aliases, "Go /x").unwrap(), expected);
+        assert!(expand(&aliases, "Go").is_err());
+    }
+
+    #[test]
+    fn recursion_guard() {
+        // An alias can wrap a command of the same name
+        assert_eq!(expand(&[("Home", "Home; Refresh")], "Home").unwrap(), ["Home", "Refresh"]);
+        assert_eq!(expand(&[("A", "B"), ("B", "A x")], "A").unwrap(), ["A x"]);
+    }
+}
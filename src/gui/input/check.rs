@@ -0,0 +1,189 @@
+use std::collections::hash_map;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use ahash::AHashMap;
+
+use super::commands::Command;
+use super::{alias, parse_modifiers};
+use crate::config::{self, CONFIG, Shortcut};
+use crate::gui::keys::parse_sequence;
+use crate::gui::{Gui, menu, show_warning};
+
+const MODIFIERS: [&str; 5] = ["control", "alt", "shift", "super", "command"];
+
+// For --check-config. Prints every problem and returns the exit code.
+pub fn check_config() -> i32 {
+    // CONFIG panics on a config that can't be parsed
+    if let Err(e) = config::load_config() {
+        eprintln!("{e}");
+        return 1;
+    }
+
+    let mut problems = config_problems();
+    problems.extend(menu::check_actions());
+
+    if problems.is_empty() {
+        println!("No problems found");
+        return 0;
+    }
+
+    for p in &problems {
+        eprintln!("{p}");
+    }
+    1
+}
+
+impl Gui {
+    // Startup only reports problems with the config, the menu already reports broken actions.
+    pub(super) fn warn_config_problems(&self) {
+        let problems = config_problems();
+        if !problems.is_empty() {
+            show_warning(format!("Problems found in config:\n{}", problems.join("\n")));
+        }
+    }
+}
+
+fn config_problems() -> Vec<String> {
+    let mut problems = Vec::new();
+
+    check_shortcuts("Shortcut", &CONFIG.shortcuts, &mut problems);
+    check_shortcuts("Chooser shortcut", &CONFIG.chooser_shortcuts, &mut problems);
+
+    let mut buttons = AHashMap::new();
+    for b in &CONFIG.mouse_buttons {
+        let binding = format!("Mouse button {:?}", describe(&b.modifiers, &b.button.to_string()));
+
+        if let Err(e) = check_modifiers(&b.modifiers) {
+            problems.push(format!("{binding}: {e}"));
+        }
+
+        match buttons.entry((parse_modifiers(&b.modifiers), b.button)) {
+            hash_map::Entry::Occupied(e) => problems.push(shadowed(&binding, e.get(), &b.action)),
+            hash_map::Entry::Vacant(e) => {
+                e.insert(&b.action);
+            }
+        }
+
+        check_action(&binding, &b.action, &mut problems);
+    }
+
+    for b in CONFIG.bookmarks.iter().chain(CONFIG.chooser_bookmarks.iter().flatten()) {
+        check_action(&format!("Bookmark {:?}", b.name), &b.action, &mut problems);
+    }
+
+    for c in &CONFIG.context_menu {
+        check_action(&format!("Context menu entry {:?}", c.name), &c.action, &mut problems);
+    }
+
+    problems
+}
+
+// Chooser shortcuts are meant to replace regular shortcuts, so each list is checked on its own.
+fn check_shortcuts(list: &str, shortcuts: &[Shortcut], problems: &mut Vec<String>) {
+    let mut seen = AHashMap::new();
+    let mut parsed = Vec::new();
+
+    for s in shortcuts {
+        let binding = format!("{list} {:?}", describe(&s.modifiers, &s.key));
+        let binding = match &s.mode {
+            Some(mode) => format!("{binding} in mode {mode:?}"),
+            None => binding,
+        };
+
+        if let Err(e) = check_modifiers(&s.modifiers) {
+            problems.push(format!("{binding}: {e}"));
+        }
+
+        match parse_sequence(s) {
+            Ok(keys) => {
+                match seen.entry((s.mode.as_deref(), keys.clone())) {
+                    hash_map::Entry::Occupied(e) => {
+                        problems.push(shadowed(&binding, e.get(), &s.action));
+                    }
+                    hash_map::Entry::Vacant(e) => {
+                        e.insert(&s.action);
+                    }
+                }
+                parsed.push((binding.clone(), s.mode.as_deref(), keys));
+            }
+            Err(e) => problems.push(format!("{binding}: {e}")),
+        }
+
+        check_action(&binding, &s.action, problems);
+    }
+
+    // The first complete match runs immediately, so longer sequences can be cut off
+    for (binding, mode, keys) in parsed {
+        for len in 1..keys.len() {
+            let prefix = keys[..len].to_vec();
+            let cut = seen.get(&(mode, prefix.clone())).or_else(|| seen.get(&(None, prefix)));
+
+            if let Some(action) = cut {
+                problems.push(format!("{binding}: unreachable, the first keys run {action:?}"));
+                break;
+            }
+        }
+    }
+}
+
+fn describe(modifiers: &Option<String>, key: &str) -> String {
+    modifiers.as_ref().map_or_else(|| key.to_string(), |m| format!("{m}+{key}"))
+}
+
+fn shadowed(binding: &str, first: &str, second: &str) -> String {
+    format!("{binding}: bound to both {first:?} and {second:?}, only the last is used")
+}
+
+// parse_modifiers silently ignores anything it doesn't recognize.
+fn check_modifiers(modifiers: &Option<String>) -> Result<(), String> {
+    let Some(m) = modifiers else {
+        return Ok(());
+    };
+
+    let unknown: Vec<_> = m
+        .split(|c: char| c == ',' || c == '+' || c == '|' || c.is_whitespace())
+        .filter(|w| !w.is_empty() && !MODIFIERS.contains(&w.to_lowercase().as_str()))
+        .collect();
+
+    if unknown.is_empty() { Ok(()) } else { Err(format!("unknown modifiers {unknown:?}")) }
+}
+
+fn check_action(binding: &str, action: &str, problems: &mut Vec<String>) {
+    let steps = match alias::expand(action) {
+        Ok(steps) => steps,
+        Err(e) => return problems.push(format!("{binding}: {e}")),
+    };
+
+    for step in steps {
+        if let Err(e) = check_step(&step) {
+            problems.push(format!("{binding}: {e}"));
+        }
+    }
+}
+
+// Parses the step like run_command does without running anything.
+fn check_step(step: &str) -> Result<(), String> {
+    match Command::parse(step)?.1 {
+        Command::Execute(exe) | Command::Script(exe) | Command::InteractiveScript(exe) => {
+            check_executable(exe)
+        }
+        Command::Mode(mode) => {
+            let mut shortcuts = CONFIG.shortcuts.iter().chain(&CONFIG.chooser_shortcuts);
+            if shortcuts.any(|s| s.mode.as_deref() == Some(mode)) {
+                Ok(())
+            } else {
+                Err(format!("Mode {mode:?} has no shortcuts"))
+            }
+        }
+        _ => Ok(()),
+    }
+}
+
+fn check_executable(path: &Path) -> Result<(), String> {
+    match path.metadata() {
+        Ok(m) if m.is_file() && m.permissions().mode() & 0o111 != 0 => Ok(()),
+        Ok(_) => Err(format!("{path:?} is not an executable file")),
+        Err(e) => Err(format!("Could not find executable {path:?}: {e}")),
+    }
+}
@@ -0,0 +1,292 @@
+use std::cmp::min;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use gtk::gdk::{Key, ModifierType};
+use gtk::glib::{Propagation, WeakRef};
+use gtk::prelude::*;
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+
+use super::commands::{self, Arg, COMMANDS};
+use crate::com::{ActionTarget, CompletionResult, CompletionTarget, ManagerAction};
+use crate::config::CONFIG;
+use crate::gui::Gui;
+use crate::gui::session::LAST_SESSION;
+
+#[derive(Debug, Default)]
+pub struct CommandLine {
+    // Only set while the command line is open
+    target: Option<ActionTarget>,
+    previous_focus: Option<WeakRef<gtk::Widget>>,
+    // Oldest first
+    history: Vec<String>,
+    // The position in history and whatever was typed before browsing it
+    browsing: Option<(usize, String)>,
+    completion: Option<Completion>,
+    // The text in front of the path while the manager is completing it
+    completing_path: Option<String>,
+}
+
+#[derive(Debug)]
+struct Completion {
+    // The last candidate is always what was originally typed
+    candidates: Vec<String>,
+    position: usize,
+}
+
+impl Gui {
+    pub(super) fn setup_command_line(self: &Rc<Self>) {
+        let entry = &self.window.imp().command_line;
+
+        let keys = gtk::EventControllerKey::new();
+        let g = self.clone();
+        keys.connect_key_pressed(move |_, key, _, mods| g.command_line_key(key, mods));
+        // Tab would otherwise move focus
+        keys.set_propagation_phase(gtk::PropagationPhase::Capture);
+        entry.add_controller(keys);
+
+        // Switching to another window shouldn't throw away what was typed
+        let focus = gtk::EventControllerFocus::new();
+        let g = self.clone();
+        focus.connect_leave(move |_| {
+            if g.window.is_active() {
+                g.close_command_line();
+            }
+        });
+        entry.add_controller(focus);
+
+        let g = self.clone();
+        entry.connect_changed(move |_| {
+            let mut state = g.command_line.borrow_mut();
+            state.completion = None;
+            if state.completing_path.take().is_some() {
+                drop(state);
+                g.send_manager(ManagerAction::CancelCompletion);
+            }
+        });
+
+        let g = self.clone();
+        entry.connect_activate(move |e| {
+            let Some(target) = g.command_line.borrow().target else {
+                return;
+            };
+
+            let command = e.text().trim().to_string();
+            g.close_command_line();
+
+            if !command.is_empty() {
+                g.database.add_command(command.clone());
+                g.run_action(target, &command);
+            }
+        });
+    }
+
+    pub(super) fn open_command_line(&self, target: ActionTarget, text: &str) {
+        let entry = &self.window.imp().command_line;
+
+        let mut state = self.command_line.borrow_mut();
+        if state.target.is_none() {
+            state.previous_focus = GtkWindowExt::focus(&self.window).map(|w| w.downgrade());
+        }
+        state.target = Some(target);
+        state.history = self.database.command_history();
+        state.browsing = None;
+        drop(state);
+
+        entry.set_text(text);
+        entry.set_visible(true);
+        entry.grab_focus_without_selecting();
+        entry.set_position(-1);
+    }
+
+    fn close_command_line(&self) {
+        let mut state = self.command_line.borrow_mut();
+        if state.target.take().is_none() {
+            return;
+        }
+
+        state.browsing = None;
+        let previous_focus = state.previous_focus.take();
+        drop(state);
+
+        let entry = &self.window.imp().command_line;
+        // Also cancels any completion
+        entry.set_text("");
+        entry.set_visible(false);
+
+        if let Some(w) = previous_focus.and_then(|w| w.upgrade()) {
+            w.grab_focus();
+        }
+    }
+
+    fn command_line_key(&self, key: Key, mods: ModifierType) -> Propagation {
+        let mods = mods & !ModifierType::LOCK_MASK;
+        if !(mods & !ModifierType::SHIFT_MASK).is_empty() {
+            return Propagation::Proceed;
+        }
+
+        match key {
+            Key::Escape => self.close_command_line(),
+            Key::Tab | Key::KP_Tab => self.complete_command_line(false),
+            Key::ISO_Left_Tab => self.complete_command_line(true),
+            Key::Up | Key::KP_Up => self.browse_command_history(true),
+            Key::Down | Key::KP_Down => self.browse_command_history(false),
+            _ => return Propagation::Proceed,
+        }
+
+        Propagation::Stop
+    }
+
+    fn browse_command_history(&self, older: bool) {
+        let entry = &self.window.imp().command_line;
+
+        let mut state = self.command_line.borrow_mut();
+        let newest = state.history.len();
+        let (position, typed) =
+            state.browsing.take().unwrap_or_else(|| (newest, entry.text().into()));
+
+        let position = if older { position.saturating_sub(1) } else { min(position + 1, newest) };
+        let text = if position == newest {
+            typed
+        } else {
+            let text = state.history[position].clone();
+            state.browsing = Some((position, typed));
+            text
+        };
+        drop(state);
+
+        entry.set_text(&text);
+        entry.set_position(-1);
+    }
+
+    fn complete_command_line(&self, backwards: bool) {
+        let entry = &self.window.imp().command_line;
+        let text = entry.text().to_string();
+
+        let mut state = self.command_line.borrow_mut();
+        let Some(target) = state.target else {
+            return;
+        };
+
+        if let Some(mut c) = state.completion.take()
+            && c.candidates[c.position] == text
+        {
+            if backwards {
+                c.position = c.position.checked_sub(1).unwrap_or(c.candidates.len() - 1);
+            } else {
+                c.position = (c.position + 1) % c.candidates.len();
+            }
+            drop(state);
+
+            return self.show_command_completion(c);
+        }
+        drop(state);
+
+        // Only the last command in a chain is completed
+        let start = text.rfind(';').map_or(0, |i| i + 1);
+        let step = text[start..].trim_start();
+        let head = &text[..text.len() - step.len()];
+
+        let Some((cmd, arg)) = step.split_once(' ') else {
+            let names = COMMANDS
+                .iter()
+                .map(|s| s.name.to_string())
+                .chain(CONFIG.aliases.keys().cloned())
+                .collect();
+
+            return self.complete_words(head, step, names, backwards);
+        };
+
+        let arg = arg.trim_start();
+        let head = &text[..text.len() - arg.len()];
+
+        let spec_arg = commands::find(cmd).map(|s| s.arg);
+
+        // Paths are completed like the location bar
+        if let Some(Arg::Path(_)) = spec_arg {
+            let mut path = PathBuf::from(arg);
+            if path.is_relative() {
+                let Some(dir) = self.tabs.borrow().target_dir(target) else {
+                    return;
+                };
+                path = dir.join(path);
+            }
+
+            self.command_line.borrow_mut().completing_path = Some(head.to_string());
+
+            let recent = self.recent_dirs.borrow().clone();
+            let target = CompletionTarget::CommandLine;
+            return self.send_manager(ManagerAction::Complete(path, arg.into(), target, recent));
+        }
+
+        let words = match cmd {
+            "LoadSession" => self.database.session_names(),
+            "SaveSession" | "DeleteSession" => {
+                let mut names = self.database.session_names();
+                names.retain(|n| n != LAST_SESSION);
+                names
+            }
+            "SaveSearch" | "OpenSearch" | "DeleteSearch" => {
+                self.saved_searches.borrow().iter().map(|s| s.name.clone()).collect()
+            }
+            "Mode" => CONFIG
+                .shortcuts
+                .iter()
+                .chain(&CONFIG.chooser_shortcuts)
+                .filter_map(|s| s.mode.clone())
+                .collect(),
+            _ => match spec_arg {
+                Some(Arg::Choice(values)) => values.iter().map(|v| v.to_string()).collect(),
+                _ => Vec::new(),
+            },
+        };
+
+        self.complete_words(head, arg, words, backwards);
+    }
+
+    fn complete_words(&self, head: &str, typed: &str, mut words: Vec<String>, backwards: bool) {
+        let lower = typed.to_lowercase();
+        words.retain(|w| w != typed && w.to_lowercase().starts_with(&lower));
+        words.sort_unstable();
+        words.dedup();
+
+        if words.is_empty() {
+            return;
+        }
+
+        let mut candidates: Vec<_> = words.into_iter().map(|w| format!("{head}{w}")).collect();
+        let position = if backwards { candidates.len() - 1 } else { 0 };
+        candidates.push(format!("{head}{typed}"));
+
+        self.show_command_completion(Completion { candidates, position });
+    }
+
+    pub fn command_line_completed(&self, completed: CompletionResult) {
+        let entry = &self.window.imp().command_line;
+
+        let Some(head) = self.command_line.borrow_mut().completing_path.take() else {
+            return trace!("Discarding completion result for closed command line");
+        };
+
+        if entry.text() != format!("{head}{}", completed.initial) {
+            return trace!("Discarding completion result since text has changed");
+        }
+
+        let candidates = completed
+            .candidates
+            .iter()
+            .map(|p| format!("{head}{}", p.to_string_lossy()))
+            .collect();
+
+        self.show_command_completion(Completion { candidates, position: completed.position });
+    }
+
+    fn show_command_completion(&self, completion: Completion) {
+        let entry = &self.window.imp().command_line;
+
+        // Changing the text clears the completion, so it's put back afterwards
+        entry.set_text(&completion.candidates[completion.position]);
+        entry.set_position(-1);
+        self.command_line.borrow_mut().completion = Some(completion);
+    }
+}
@@ -0,0 +1,425 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use gtk::Orientation;
+use strum::VariantNames;
+use strum_macros::{EnumString, VariantNames};
+
+use crate::com::{DisplayMode, SortDir, SortMode};
+use crate::gui::tabs::list::{NamePattern, PaneDirection, ResizeAmount, SelectMode};
+
+// What can follow the name of a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Arg {
+    None,
+    // Exactly one of these values
+    Choice(&'static [&'static str]),
+    // Anything, described by the hint
+    Text(&'static str),
+    // A path, completed like the location bar
+    Path(&'static str),
+}
+
+#[derive(Debug)]
+pub(super) struct Spec {
+    pub name: &'static str,
+    pub arg: Arg,
+    // Whether the argument can be left out
+    pub optional: bool,
+    // Whether it can be used when running as a file chooser
+    pub chooser: bool,
+}
+
+impl Spec {
+    const fn new(name: &'static str, arg: Arg) -> Self {
+        Self { name, arg, optional: false, chooser: true }
+    }
+
+    const fn bare(name: &'static str) -> Self {
+        Self { optional: true, ..Self::new(name, Arg::None) }
+    }
+
+    const fn optional(self) -> Self {
+        Self { optional: true, ..self }
+    }
+
+    const fn not_in_chooser(self) -> Self {
+        Self { chooser: false, ..self }
+    }
+}
+
+#[derive(Debug, Clone, Copy, EnumString, VariantNames)]
+#[strum(serialize_all = "lowercase")]
+enum SplitDirection {
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, VariantNames)]
+#[strum(serialize_all = "lowercase")]
+pub(super) enum FocusTarget {
+    Next,
+    Prev,
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+// Every command that can be bound, typed or run by scripts, in the order they're listed.
+// Prompt, Confirm and Choose are only understood by interactive scripts.
+pub(super) const COMMANDS: &[Spec] = &[
+    Spec::bare("Quit"),
+    Spec::bare("Help"),
+    Spec::bare("CommandPalette"),
+    Spec::new("CommandLine", Arg::Text("<text>")).optional(),
+    Spec::new("Mode", Arg::Text("<name>")),
+    Spec::bare("ExitMode"),
+    Spec::bare("RunningScripts"),
+    Spec::bare("KillScripts"),
+    Spec::bare("Activate"),
+    Spec::bare("OpenDefault"),
+    Spec::bare("OpenWith"),
+    Spec::bare("Copy"),
+    Spec::bare("Cut"),
+    Spec::bare("Paste"),
+    Spec::bare("Cancel"),
+    Spec::bare("Undo"),
+    Spec::bare("Home"),
+    Spec::new("Navigate", Arg::Path("<path>")),
+    Spec::new("JumpTo", Arg::Path("<path>")),
+    Spec::new("NewTab", Arg::Path("<path>")).optional(),
+    Spec::new("NewBackgroundTab", Arg::Path("<path>")).optional(),
+    Spec::bare("ReopenTab"),
+    Spec::bare("Refresh"),
+    Spec::bare("RefreshAll"),
+    Spec::bare("ReloadActions"),
+    Spec::bare("ReloadConfig"),
+    Spec::new("Display", Arg::Choice(DisplayMode::VARIANTS)),
+    Spec::new("SortBy", Arg::Choice(SortMode::VARIANTS)),
+    Spec::new("SortDir", Arg::Choice(SortDir::VARIANTS)),
+    Spec::new("Split", Arg::Choice(SplitDirection::VARIANTS)),
+    Spec::new("MovePane", Arg::Choice(PaneDirection::VARIANTS)),
+    Spec::bare("SwapPanes"),
+    Spec::bare("RotateSplit"),
+    Spec::new("FocusPane", Arg::Choice(FocusTarget::VARIANTS)),
+    Spec::new("ResizePane", Arg::Text("<direction> <amount>")),
+    Spec::bare("EqualizePanes"),
+    Spec::bare("CopyToOtherPane"),
+    Spec::bare("MoveToOtherPane"),
+    Spec::bare("LinkToOtherPane"),
+    Spec::bare("SyncOtherPane"),
+    Spec::bare("CloseTab"),
+    Spec::bare("ClosePane"),
+    Spec::bare("HidePanes"),
+    Spec::bare("CloseActive"),
+    Spec::bare("CloseTabNoReplacement"),
+    Spec::bare("Forward"),
+    Spec::bare("Back"),
+    Spec::bare("Parent"),
+    Spec::bare("Child"),
+    Spec::bare("BackOrParent"),
+    Spec::bare("Trash"),
+    Spec::bare("Delete"),
+    Spec::bare("Rename"),
+    Spec::bare("Properties"),
+    Spec::bare("FocusLocation"),
+    Spec::bare("Unselect"),
+    Spec::bare("SelectAll"),
+    Spec::bare("InvertSelection"),
+    Spec::bare("SelectSameType"),
+    Spec::new("Select", Arg::Text("<pattern>")),
+    Spec::new("AddToSelection", Arg::Text("<pattern>")),
+    Spec::new("RemoveFromSelection", Arg::Text("<pattern>")),
+    Spec::new("SelectPaths", Arg::Text("<paths>")),
+    Spec::bare("NewFolder"),
+    Spec::bare("NewFile"),
+    Spec::new("Search", Arg::Text("<query>")).optional(),
+    Spec::bare("JumpRecent").not_in_chooser(),
+    Spec::new("SaveSession", Arg::Text("<name>")),
+    Spec::new("LoadSession", Arg::Text("<name>")),
+    Spec::new("DeleteSession", Arg::Text("<name>")),
+    Spec::new("SaveSearch", Arg::Text("<name>")).not_in_chooser(),
+    Spec::new("OpenSearch", Arg::Text("<name>")).not_in_chooser(),
+    Spec::new("DeleteSearch", Arg::Text("<name>")).not_in_chooser(),
+    Spec::new("Execute", Arg::Path("<executable>")),
+    Spec::new("Script", Arg::Path("<executable>")),
+    Spec::new("InteractiveScript", Arg::Path("<executable>")),
+];
+
+pub(super) const INTERACTIVE_ONLY: [&str; 3] = ["Prompt", "Confirm", "Choose"];
+
+pub(super) fn find(name: &str) -> Option<&'static Spec> {
+    COMMANDS.iter().find(|s| s.name == name)
+}
+
+// A single command with its argument parsed, see COMMANDS.
+#[derive(Debug)]
+pub(super) enum Command<'a> {
+    Quit,
+    Help,
+    CommandPalette,
+    CommandLine(&'a str),
+    Mode(&'a str),
+    ExitMode,
+    RunningScripts,
+    KillScripts,
+    Activate,
+    OpenDefault,
+    OpenWith,
+    Copy,
+    Cut,
+    Paste,
+    Cancel,
+    Undo,
+    Home,
+    Navigate(&'a Path),
+    JumpTo(&'a Path),
+    NewTab(Option<&'a Path>),
+    NewBackgroundTab(Option<&'a Path>),
+    ReopenTab,
+    Refresh,
+    RefreshAll,
+    ReloadActions,
+    ReloadConfig,
+    Display(DisplayMode),
+    SortBy(SortMode),
+    SortDir(SortDir),
+    Split(Orientation),
+    MovePane(PaneDirection),
+    SwapPanes,
+    RotateSplit,
+    FocusPane(FocusTarget),
+    ResizePane(PaneDirection, ResizeAmount),
+    EqualizePanes,
+    CopyToOtherPane,
+    MoveToOtherPane,
+    LinkToOtherPane,
+    SyncOtherPane,
+    CloseTab,
+    ClosePane,
+    HidePanes,
+    CloseTabNoReplacement,
+    Forward,
+    Back,
+    Parent,
+    Child,
+    BackOrParent,
+    Trash,
+    Delete,
+    Rename,
+    Properties,
+    FocusLocation,
+    Unselect,
+    SelectAll,
+    InvertSelection,
+    SelectSameType,
+    Select(NamePattern, SelectMode),
+    SelectPaths(&'a str),
+    NewFolder,
+    NewFile,
+    Search(&'a str),
+    JumpRecent,
+    SaveSession(&'a str),
+    LoadSession(&'a str),
+    DeleteSession(&'a str),
+    SaveSearch(&'a str),
+    OpenSearch(&'a str),
+    DeleteSearch(&'a str),
+    Execute(&'a Path),
+    Script(&'a Path),
+    InteractiveScript(&'a Path),
+}
+
+impl<'a> Command<'a> {
+    pub(super) fn parse(line: &'a str) -> Result<(&'static Spec, Self), String> {
+        // Do not trim the end because files and directories can end in spaces
+        let line = line.trim_start();
+        let (name, arg) = match line.split_once(' ') {
+            Some((name, arg)) => (name, Some(arg.trim_start()).filter(|a| !a.is_empty())),
+            None => (line, None),
+        };
+
+        if INTERACTIVE_ONLY.contains(&name) {
+            return Err(format!("{name} can only be used by interactive scripts"));
+        }
+
+        let Some(spec) = find(name) else {
+            return Err(format!("Unrecognized command {line:?}"));
+        };
+
+        let arg = match (spec.arg, arg) {
+            (Arg::None, Some(_)) => return Err(format!("{name} doesn't take an argument")),
+            (Arg::None, None) => "",
+            (_, Some(arg)) => arg,
+            (_, None) if spec.optional => "",
+            (Arg::Choice(values), None) => {
+                return Err(format!("{name} requires one of: {}", values.join(", ")));
+            }
+            (Arg::Text(hint) | Arg::Path(hint), None) => {
+                return Err(format!("{name} requires an argument {hint}"));
+            }
+        };
+        let path = Path::new(arg);
+        let given_path = (!arg.is_empty()).then_some(path);
+
+        let invalid = || format!("Invalid argument {arg:?} for {name}");
+        let pattern = || NamePattern::from_str(arg).map_err(|e| format!("{name}: {e}"));
+
+        let command = match name {
+            "Quit" => Self::Quit,
+            "Help" => Self::Help,
+            "CommandPalette" => Self::CommandPalette,
+            "CommandLine" => Self::CommandLine(arg),
+            "Mode" => Self::Mode(arg),
+            "ExitMode" => Self::ExitMode,
+            "RunningScripts" => Self::RunningScripts,
+            "KillScripts" => Self::KillScripts,
+            "Activate" => Self::Activate,
+            "OpenDefault" => Self::OpenDefault,
+            "OpenWith" => Self::OpenWith,
+            "Copy" => Self::Copy,
+            "Cut" => Self::Cut,
+            "Paste" => Self::Paste,
+            "Cancel" => Self::Cancel,
+            "Undo" => Self::Undo,
+            "Home" => Self::Home,
+            "Navigate" => Self::Navigate(path),
+            "JumpTo" => Self::JumpTo(path),
+            "NewTab" => Self::NewTab(given_path),
+            "NewBackgroundTab" => Self::NewBackgroundTab(given_path),
+            "ReopenTab" => Self::ReopenTab,
+            "Refresh" => Self::Refresh,
+            "RefreshAll" => Self::RefreshAll,
+            "ReloadActions" => Self::ReloadActions,
+            "ReloadConfig" => Self::ReloadConfig,
+            "Display" => Self::Display(arg.parse().map_err(|_e| invalid())?),
+            "SortBy" => Self::SortBy(arg.parse().map_err(|_e| invalid())?),
+            "SortDir" => Self::SortDir(arg.parse().map_err(|_e| invalid())?),
+            "Split" => match arg.parse().map_err(|_e| invalid())? {
+                SplitDirection::Horizontal => Self::Split(Orientation::Horizontal),
+                SplitDirection::Vertical => Self::Split(Orientation::Vertical),
+            },
+            "MovePane" => Self::MovePane(arg.parse().map_err(|_e| invalid())?),
+            "SwapPanes" => Self::SwapPanes,
+            "RotateSplit" => Self::RotateSplit,
+            "FocusPane" => Self::FocusPane(arg.parse().map_err(|_e| invalid())?),
+            "ResizePane" => {
+                let (dir, amount) = arg.split_once(' ').ok_or_else(invalid)?;
+                let dir = dir.parse().map_err(|_e| invalid())?;
+                let amount = amount.trim().parse().map_err(|()| invalid())?;
+                Self::ResizePane(dir, amount)
+            }
+            "EqualizePanes" => Self::EqualizePanes,
+            "CopyToOtherPane" => Self::CopyToOtherPane,
+            "MoveToOtherPane" => Self::MoveToOtherPane,
+            "LinkToOtherPane" => Self::LinkToOtherPane,
+            "SyncOtherPane" => Self::SyncOtherPane,
+            "CloseTab" => Self::CloseTab,
+            "ClosePane" => Self::ClosePane,
+            "HidePanes" => Self::HidePanes,
+            "CloseActive" | "CloseTabNoReplacement" => Self::CloseTabNoReplacement,
+            "Forward" => Self::Forward,
+            "Back" => Self::Back,
+            "Parent" => Self::Parent,
+            "Child" => Self::Child,
+            "BackOrParent" => Self::BackOrParent,
+            "Trash" => Self::Trash,
+            "Delete" => Self::Delete,
+            "Rename" => Self::Rename,
+            "Properties" => Self::Properties,
+            "FocusLocation" => Self::FocusLocation,
+            "Unselect" => Self::Unselect,
+            "SelectAll" => Self::SelectAll,
+            "InvertSelection" => Self::InvertSelection,
+            "SelectSameType" => Self::SelectSameType,
+            "Select" => Self::Select(pattern()?, SelectMode::Replace),
+            "AddToSelection" => Self::Select(pattern()?, SelectMode::Add),
+            "RemoveFromSelection" => Self::Select(pattern()?, SelectMode::Remove),
+            "SelectPaths" => Self::SelectPaths(arg),
+            "NewFolder" => Self::NewFolder,
+            "NewFile" => Self::NewFile,
+            "Search" => Self::Search(arg),
+            "JumpRecent" => Self::JumpRecent,
+            "SaveSession" => Self::SaveSession(arg),
+            "LoadSession" => Self::LoadSession(arg),
+            "DeleteSession" => Self::DeleteSession(arg),
+            "SaveSearch" => Self::SaveSearch(arg),
+            "OpenSearch" => Self::OpenSearch(arg),
+            "DeleteSearch" => Self::DeleteSearch(arg),
+            "Execute" => Self::Execute(path),
+            "Script" => Self::Script(path),
+            "InteractiveScript" => Self::InteractiveScript(path),
+            // Caught by the tests
+            _ => return Err(format!("Unrecognized command {line:?}")),
+        };
+
+        Ok((spec, command))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ahash::AHashSet;
+
+    use super::{Arg, COMMANDS, Command, INTERACTIVE_ONLY};
+
+    fn parses(line: &str) -> bool {
+        Command::parse(line).is_ok()
+    }
+
+    #[test]
+    fn unique_names() {
+        let names: AHashSet<_> = COMMANDS.iter().map(|s| s.name).collect();
+        assert_eq!(names.len(), COMMANDS.len());
+    }
+
+    #[test]
+    fn every_command_parses() {
+        for spec in COMMANDS {
+            let name = spec.name;
+            assert_eq!(parses(name), spec.optional || spec.arg == Arg::None, "{name}");
+
+            match spec.arg {
+                Arg::None => assert!(!parses(&format!("{name} arg")), "{name}"),
+                Arg::Choice(values) => {
+                    assert!(!values.is_empty(), "{name}");
+                    for v in values {
+                        assert!(parses(&format!("{name} {v}")), "{name} {v}");
+                    }
+                    assert!(!parses(&format!("{name} nonsense")), "{name}");
+                }
+                Arg::Text(_) | Arg::Path(_) => {
+                    let arg = if name == "ResizePane" { "right 10%" } else { "a" };
+                    assert!(parses(&format!("{name} {arg}")), "{name}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn arguments() {
+        assert!(parses("  SortBy   mtime"));
+        assert!(parses("ResizePane left -20"));
+        assert!(!parses("ResizePane left"));
+        assert!(!parses("ResizePane sideways 10"));
+        assert!(!parses("Select re:("));
+        assert!(matches!(Command::parse("NewTab"), Ok((_, Command::NewTab(None)))));
+        assert!(matches!(Command::parse("Search"), Ok((_, Command::Search("")))));
+        // Paths can end in spaces
+        assert!(matches!(
+            Command::parse("Navigate /tmp/dir "),
+            Ok((_, Command::Navigate(p))) if p.as_os_str() == "/tmp/dir "
+        ));
+    }
+
+    #[test]
+    fn unknown_commands() {
+        assert!(!parses(""));
+        assert!(!parses("NotACommand"));
+        assert!(!parses("quit"));
+        for name in INTERACTIVE_ONLY {
+            assert!(!parses(&format!("{name} Title")));
+        }
+    }
+}
@@ -35,7 +35,7 @@ impl Gui {
         let mut seen = AHashSet::with_capacity(CONFIG.shortcuts.len());
 
         let mut handle = |s: &'static Shortcut| {
-            let key = (parse_modifiers(&s.modifiers), &s.key);
+            let key = (parse_modifiers(&s.modifiers), &s.key, &s.mode);
             if seen.insert(key) {
                 shortcuts.push_front(s);
             }
@@ -80,7 +80,7 @@ impl Gui {
             let entry = item.item().and_downcast::<BoxedAnyObject>().unwrap();
             let s: Ref<&Shortcut> = entry.borrow();
 
-            match Key::from_name(&s.key).unwrap().to_unicode() {
+            match Key::from_name(&s.key).and_then(|k| k.to_unicode()) {
                 // This should avoid most unprintable/weird characters but still translate
                 // question, bracketright, etc into characters.
                 Some(c) if !c.is_whitespace() && !c.is_control() => {
@@ -106,7 +106,10 @@ impl Gui {
             let child = item.child().and_downcast::<gtk::Label>().unwrap();
             let entry = item.item().and_downcast::<BoxedAnyObject>().unwrap();
             let s: Ref<&Shortcut> = entry.borrow();
-            child.set_text(&s.action);
+            match &s.mode {
+                Some(mode) => child.set_text(&format!("{} ({mode} mode)", s.action)),
+                None => child.set_text(&s.action),
+            }
         });
 
         let modifier_column = gtk::ColumnViewColumn::new(Some("Modifiers"), Some(modifier_factory));
@@ -0,0 +1,128 @@
+use std::rc::Rc;
+
+use gtk::glib::{GString, Propagation};
+use gtk::prelude::*;
+
+use super::commands::{Arg, COMMANDS};
+use super::wrap_in_box_with_close_button;
+use crate::com::ActionTarget;
+use crate::config::{CONFIG, OPTIONS};
+use crate::gui::Gui;
+use crate::gui::session::LAST_SESSION;
+use crate::gui::keys::{parse_sequence, sequence_name};
+
+enum Choice {
+    Command(String),
+    Argument(&'static str, &'static str),
+    CustomAction(GString),
+}
+
+impl Gui {
+    pub(super) fn command_palette(self: &Rc<Self>) {
+        let target = self.tabs.borrow().active_action_target();
+        let (names, choices): (Vec<_>, Vec<_>) = self.palette_choices().into_iter().unzip();
+
+        let g = self.clone();
+        self.fuzzy_picker("Command Palette", names, move |i| match &choices[i] {
+            Choice::Command(cmd) => g.run_action(target, cmd),
+            Choice::Argument(cmd, hint) => g.command_argument(target, cmd, hint),
+            Choice::CustomAction(name) => g.menu.get().unwrap().activate_by_name(&g, name),
+        });
+    }
+
+    fn palette_choices(&self) -> Vec<(String, Choice)> {
+        let chooser = OPTIONS.chooser_mode.is_some();
+        let mut choices = Vec::new();
+
+        for spec in COMMANDS.iter().filter(|s| !chooser || s.chooser) {
+            let name = spec.name;
+            if spec.optional {
+                choices.push((name.to_string(), Choice::Command(name.to_string())));
+            }
+
+            match spec.arg {
+                Arg::None => {}
+                // Commands with only a few valid arguments are listed once for each of them
+                Arg::Choice(values) => {
+                    for v in values {
+                        let cmd = format!("{name} {v}");
+                        choices.push((cmd.clone(), Choice::Command(cmd)));
+                    }
+                }
+                // The rest prompt for their argument when chosen
+                Arg::Text(hint) | Arg::Path(hint) => {
+                    choices.push((format!("{name} {hint}"), Choice::Argument(name, hint)));
+                }
+            }
+        }
+
+        let bookmarks = if chooser {
+            CONFIG.chooser_bookmarks.as_ref().unwrap_or(&CONFIG.bookmarks)
+        } else {
+            &CONFIG.bookmarks
+        };
+        for b in bookmarks {
+            choices.push((format!("Bookmark: {}", b.name), Choice::Command(b.action.clone())));
+        }
+
+        let chooser_shortcuts = if chooser { &CONFIG.chooser_shortcuts[..] } else { &[] };
+        // Shortcuts bound in a mode can be run from here in any mode
+        for s in CONFIG.shortcuts.iter().chain(chooser_shortcuts) {
+            let Ok(keys) = parse_sequence(s) else {
+                continue;
+            };
+
+            let name = format!("Shortcut {}: {}", sequence_name(&keys), s.action);
+            choices.push((name, Choice::Command(s.action.clone())));
+        }
+
+        for (name, action) in self.menu.get().unwrap().applicable_actions(self) {
+            choices.push((format!("Action: {name}"), Choice::CustomAction(action)));
+        }
+
+        // The last session is restored automatically
+        for name in self.database.session_names().into_iter().filter(|n| n != LAST_SESSION) {
+            let cmd = format!("LoadSession {name}");
+            choices.push((format!("Session: {name}"), Choice::Command(cmd)));
+        }
+
+        choices
+    }
+
+    fn command_argument(self: &Rc<Self>, target: ActionTarget, cmd: &str, hint: &str) {
+        let dialog =
+            gtk::Window::builder().title(cmd).transient_for(&self.window).modal(true).build();
+        dialog.set_default_width(800);
+
+        self.close_on_quit_or_esc(&dialog);
+
+        let entry = gtk::Entry::new();
+        entry.set_placeholder_text(Some(hint));
+
+        let g = self.clone();
+        let d = dialog.downgrade();
+        let cmd = cmd.to_string();
+        let run = move |e: &gtk::Entry| {
+            let command = format!("{cmd} {}", e.text());
+            d.upgrade().unwrap().destroy();
+            g.run_command(target, &command);
+        };
+        // activates-default is slow, so clone this closure instead
+        entry.connect_activate(run.clone());
+
+        let actions = wrap_in_box_with_close_button(&dialog, entry.clone(), "Cancel");
+
+        let confirm = gtk::Button::with_label("Run");
+        let e = entry.clone();
+        confirm.connect_clicked(move |_| run(&e));
+        actions.append(&confirm);
+
+        dialog.connect_close_request(move |d| {
+            d.destroy();
+            Propagation::Proceed
+        });
+
+        dialog.set_visible(true);
+        entry.grab_focus();
+    }
+}
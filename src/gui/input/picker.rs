@@ -0,0 +1,222 @@
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::rc::Rc;
+
+use gtk::gdk::Key;
+use gtk::glib::Propagation;
+use gtk::pango::EllipsizeMode;
+use gtk::prelude::*;
+use gtk::{ListScrollFlags, Orientation, SingleSelection, StringList, StringObject};
+
+use super::wrap_in_box_with_close_button;
+use crate::config::DIALOG_RES;
+use crate::gui::{Gui, label_attributes};
+
+impl Gui {
+    // Shows the choices filtered and ranked by fuzzy matching against the entry.
+    // on_choose is called with the index of the chosen item in choices.
+    pub(super) fn fuzzy_picker(
+        self: &Rc<Self>,
+        title: &str,
+        choices: Vec<String>,
+        on_choose: impl Fn(usize) + 'static,
+    ) -> gtk::Window {
+        let dialog = gtk::Window::builder()
+            .title(title)
+            .transient_for(&self.window)
+            .modal(true)
+            .build();
+
+        let res = *DIALOG_RES;
+        dialog.set_default_width(res.0);
+        dialog.set_default_height(res.1);
+
+        self.close_on_quit_or_esc(&dialog);
+
+        let list = StringList::new(&[]);
+        let selection = SingleSelection::new(Some(list.clone()));
+        // Maps rows in the list back to indices in choices
+        let shown: Rc<RefCell<Vec<usize>>> = Rc::default();
+
+        let filter = {
+            let (selection, shown) = (selection.clone(), shown.clone());
+            move |query: &str| {
+                let mut matches: Vec<_> = choices
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, c)| fuzzy_score(query, c).map(|score| (score, i)))
+                    .collect();
+
+                // Stable, so ties stay in their original order
+                matches.sort_by_key(|(score, _)| Reverse(*score));
+
+                let strs: Vec<&str> = matches.iter().map(|(_, i)| &*choices[*i]).collect();
+                list.splice(0, list.n_items(), &strs);
+                *shown.borrow_mut() = matches.into_iter().map(|(_, i)| i).collect();
+
+                selection.set_selected(0);
+            }
+        };
+        filter("");
+
+        let w = dialog.downgrade();
+        let s = shown.clone();
+        let choose = Rc::new(move |pos: u32| {
+            let Some(&index) = s.borrow().get(pos as usize) else {
+                return;
+            };
+
+            // Before closing, so anything watching for the dialog to be destroyed sees the choice
+            on_choose(index);
+            if let Some(d) = w.upgrade() {
+                d.close();
+            }
+        });
+
+        let factory = gtk::SignalListItemFactory::new();
+        factory.connect_setup(move |_fact, item| {
+            let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+            let label = gtk::Label::new(None);
+            label_attributes(&label);
+
+            label.set_halign(gtk::Align::Start);
+            label.set_ellipsize(EllipsizeMode::Middle);
+            item.set_child(Some(&label))
+        });
+        factory.connect_bind(move |_fact, item| {
+            let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+            let child = item.child().and_downcast::<gtk::Label>().unwrap();
+            let entry = item.item().and_downcast::<StringObject>().unwrap();
+            child.set_text(&entry.string());
+        });
+
+        let view = gtk::ListView::new(Some(selection.clone()), Some(factory));
+
+        let c = choose.clone();
+        view.connect_activate(move |_, pos| c(pos));
+
+        let entry = gtk::Entry::new();
+        entry.connect_changed(move |e| filter(&e.text()));
+
+        let sel = selection.clone();
+        entry.connect_activate(move |_| {
+            let pos = sel.selected();
+            if pos != gtk::INVALID_LIST_POSITION {
+                choose(pos);
+            }
+        });
+
+        let v = view.clone();
+        let key = gtk::EventControllerKey::new();
+        key.connect_key_pressed(move |_e, key, _b, _mods| {
+            let n = selection.n_items();
+            if n == 0 {
+                return Propagation::Proceed;
+            }
+
+            let current = selection.selected().min(n - 1);
+            let next = match key {
+                Key::Up => current.saturating_sub(1),
+                Key::Down => (current + 1).min(n - 1),
+                _ => return Propagation::Proceed,
+            };
+
+            selection.set_selected(next);
+            v.scroll_to(next, ListScrollFlags::NONE, None);
+            Propagation::Stop
+        });
+        entry.add_controller(key);
+
+        let scrolled =
+            gtk::ScrolledWindow::builder().hscrollbar_policy(gtk::PolicyType::Never).build();
+        scrolled.set_vexpand(true);
+        scrolled.set_child(Some(&view));
+
+        let vbox = gtk::Box::new(Orientation::Vertical, 12);
+        vbox.append(&entry);
+        vbox.append(&scrolled);
+
+        wrap_in_box_with_close_button(&dialog, vbox, "Cancel");
+
+        dialog.connect_close_request(move |d| {
+            d.destroy();
+            Propagation::Proceed
+        });
+
+        dialog.set_visible(true);
+        entry.grab_focus();
+
+        dialog
+    }
+}
+
+// Case-insensitive subsequence matching.
+// Consecutive matches and matches at the start of path components or words score higher.
+pub(super) fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let mut query_chars = query.chars().flat_map(char::to_lowercase).peekable();
+
+    let mut score = 0;
+    let mut prev: Option<char> = None;
+    let mut prev_matched = false;
+
+    for c in candidate.chars() {
+        let Some(&q) = query_chars.peek() else {
+            break;
+        };
+
+        if c.to_lowercase().next() == Some(q) {
+            query_chars.next();
+            score += 1;
+
+            if prev_matched {
+                score += 5;
+            }
+
+            if prev.is_none_or(|p| matches!(p, '/' | ' ' | '_' | '-' | '.')) {
+                score += 3;
+            }
+
+            prev_matched = true;
+        } else {
+            prev_matched = false;
+        }
+
+        prev = Some(c);
+    }
+
+    query_chars.peek().is_none().then_some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fuzzy_score;
+
+    #[test]
+    fn subsequences() {
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+        assert!(fuzzy_score("abc", "a_b_c").is_some());
+        assert!(fuzzy_score("ABC", "abc").is_some());
+        assert!(fuzzy_score("abc", "ABC").is_some());
+        assert_eq!(fuzzy_score("abc", "acb"), None);
+        assert_eq!(fuzzy_score("abcd", "abc"), None);
+    }
+
+    #[test]
+    fn scores() {
+        // 1 per match, 5 per consecutive match, 3 at the start of a word
+        assert_eq!(fuzzy_score("a", "a"), Some(4));
+        assert_eq!(fuzzy_score("a", "ba"), Some(1));
+        assert_eq!(fuzzy_score("ab", "ab"), Some(10));
+        assert_eq!(fuzzy_score("ab", "a-b"), Some(8));
+        assert_eq!(fuzzy_score("ab", "axb"), Some(5));
+    }
+
+    #[test]
+    fn ordering() {
+        let score = |c| fuzzy_score("doc", c).unwrap();
+
+        assert!(score("/home/user/docs") > score("/home/user/dxoxc"));
+        assert!(score("/home/user/docs") > score("/home/user/undocumented"));
+        assert!(score("/home/user/my-docs") > score("/home/user/mydocs"));
+    }
+}
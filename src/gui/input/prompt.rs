@@ -0,0 +1,200 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use gtk::glib::Propagation;
+use gtk::pango::WrapMode;
+use gtk::prelude::*;
+use gtk::{Orientation, Window};
+use serde_json::{Value, json};
+use tokio::sync::oneshot;
+
+use super::wrap_in_box_with_close_button;
+use crate::gui::{Gui, label_attributes};
+
+// Writes the answer back to an interactive script exactly once.
+// If the dialog is closed without an answer the script gets the fallback instead.
+#[derive(Clone)]
+struct Responder(Rc<Cell<Option<oneshot::Sender<String>>>>);
+
+impl Responder {
+    fn new(respond: oneshot::Sender<String>) -> Self {
+        Self(Rc::new(Cell::new(Some(respond))))
+    }
+
+    fn respond(&self, result: Value) {
+        if let Some(respond) = self.0.take() {
+            drop(respond.send(json!({ "ok": true, "result": result }).to_string()));
+        }
+    }
+
+    fn on_destroy(&self, dialog: &Window, fallback: Value) {
+        let r = self.clone();
+        dialog.connect_destroy(move |_| r.respond(fallback.clone()));
+    }
+}
+
+impl Gui {
+    fn script_dialog(self: &Rc<Self>, title: &str, message: &str) -> (Window, gtk::Box) {
+        let dialog = Window::builder().title(title).transient_for(&self.window).modal(true).build();
+
+        self.close_on_quit_or_esc(&dialog);
+
+        dialog.set_default_width(800);
+
+        let vbox = gtk::Box::new(Orientation::Vertical, 12);
+
+        let label = gtk::Label::new(Some(message));
+        label.set_margin_start(8);
+        label.set_margin_end(8);
+        label.set_wrap(true);
+        label.set_wrap_mode(WrapMode::WordChar);
+        label_attributes(&label);
+
+        vbox.append(&label);
+
+        dialog.connect_close_request(move |d| {
+            d.destroy();
+            Propagation::Proceed
+        });
+
+        (dialog, vbox)
+    }
+
+    // Responds with the entered text, or null if cancelled.
+    pub(super) fn prompt_dialog(self: &Rc<Self>, title: &str, respond: oneshot::Sender<String>) {
+        let (dialog, vbox) = self.script_dialog("Prompt", title);
+
+        let responder = Responder::new(respond);
+        responder.on_destroy(&dialog, Value::Null);
+
+        let d = dialog.downgrade();
+        let answer = move |e: &gtk::Entry| {
+            responder.respond(e.text().as_str().into());
+            d.upgrade().unwrap().destroy();
+        };
+
+        let entry = gtk::Entry::new();
+        // activates-default is slow, so clone this closure instead
+        entry.connect_activate(answer.clone());
+
+        vbox.append(&entry);
+
+        let actions = wrap_in_box_with_close_button(&dialog, vbox, "Cancel");
+
+        let confirm = gtk::Button::with_label("OK");
+        let e = entry.clone();
+        confirm.connect_clicked(move |_| answer(&e));
+
+        actions.append(&confirm);
+
+        dialog.set_visible(true);
+
+        entry.set_enable_undo(true);
+    }
+
+    // Responds with true or false. Cancelling is the same as answering no.
+    pub(super) fn confirm_dialog(
+        self: &Rc<Self>,
+        question: &str,
+        respond: oneshot::Sender<String>,
+    ) {
+        let (dialog, vbox) = self.script_dialog("Confirm", question);
+
+        let responder = Responder::new(respond);
+        responder.on_destroy(&dialog, false.into());
+
+        let actions = wrap_in_box_with_close_button(&dialog, vbox, "No");
+
+        let yes = gtk::Button::with_label("Yes");
+        let d = dialog.downgrade();
+        yes.connect_clicked(move |_| {
+            responder.respond(true.into());
+            d.upgrade().unwrap().destroy();
+        });
+
+        actions.append(&yes);
+
+        dialog.set_visible(true);
+        yes.grab_focus();
+    }
+
+    // Responds with the chosen option, or null if cancelled.
+    pub(super) fn choose_dialog(
+        self: &Rc<Self>,
+        title: &str,
+        options: Vec<String>,
+        respond: oneshot::Sender<String>,
+    ) {
+        let responder = Responder::new(respond);
+
+        let r = responder.clone();
+        let opts = options.clone();
+        let dialog = self.fuzzy_picker(title, options, move |i| r.respond(opts[i].clone().into()));
+
+        responder.on_destroy(&dialog, Value::Null);
+    }
+}
+
+// Splits on whitespace, keeping double-quoted strings together.
+// A backslash escapes the next character.
+pub(super) fn split_quoted(s: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current: Option<String> = None;
+    let mut quoted = false;
+
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                let word = current.get_or_insert_default();
+                if let Some(next) = chars.next() {
+                    word.push(next);
+                }
+            }
+            '"' => {
+                current.get_or_insert_default();
+                quoted = !quoted;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if let Some(word) = current.take() {
+                    words.push(word);
+                }
+            }
+            c => current.get_or_insert_default().push(c),
+        }
+    }
+
+    words.extend(current);
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::split_quoted as split;
+
+    #[test]
+    fn whitespace() {
+        assert!(split("").is_empty());
+        assert!(split("   ").is_empty());
+        assert_eq!(split("a b"), ["a", "b"]);
+        assert_eq!(split("  a \t b  "), ["a", "b"]);
+    }
+
+    #[test]
+    fn quotes() {
+        assert_eq!(split(r#""Target resolution" 720p"#), ["Target resolution", "720p"]);
+        assert_eq!(split(r#"a"b c"d"#), ["ab cd"]);
+        assert_eq!(split(r#""" a"#), ["", "a"]);
+        // An unterminated quote runs to the end
+        assert_eq!(split(r#""a b"#), ["a b"]);
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(split(r"a\ b c"), ["a b", "c"]);
+        assert_eq!(split(r#"\"a\" b"#), [r#""a""#, "b"]);
+        assert_eq!(split(r"a\\b"), [r"a\b"]);
+        assert_eq!(split(r"a\"), ["a"]);
+        assert_eq!(split(r"\"), [""]);
+    }
+}
@@ -0,0 +1,178 @@
+use std::fs::{DirBuilder, remove_file};
+use std::os::unix::fs::DirBuilderExt;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use gtk::gio::{
+    DataInputStream, SocketConnection, SocketListener, SocketProtocol, SocketType,
+    UnixSocketAddress,
+};
+use gtk::glib;
+use gtk::prelude::*;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use super::{Gui, TabId, show_error};
+use crate::com::ActionTarget;
+use crate::config::{CONFIG, OPTIONS};
+use crate::ipc::{check_socket_dir, socket_path};
+
+#[derive(Debug)]
+pub(super) struct IpcServer {
+    listener: SocketListener,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Request {
+    Command {
+        command: String,
+        #[serde(default)]
+        tab: Option<u64>,
+    },
+    Query {
+        query: String,
+        #[serde(default)]
+        tab: Option<u64>,
+    },
+}
+
+impl Gui {
+    pub(super) fn ipc_listen(self: &Rc<Self>) {
+        if !CONFIG.ipc_socket || OPTIONS.chooser_mode.is_some() {
+            return;
+        }
+
+        let path = socket_path(std::process::id());
+        let dir = path.parent().unwrap();
+        if let Err(e) = DirBuilder::new().recursive(true).mode(0o700).create(dir) {
+            return show_error(format!("Failed to create IPC socket directory {dir:?}: {e}"));
+        }
+
+        // Creating it does nothing if it already exists
+        if let Err(e) = check_socket_dir(dir) {
+            return show_error(format!("Refusing to listen on IPC socket: {e}"));
+        }
+
+        // Left behind by an earlier process with the same PID
+        drop(remove_file(&path));
+
+        let listener = SocketListener::new();
+        if let Err(e) = listener.add_address(
+            &UnixSocketAddress::new(&path),
+            SocketType::Stream,
+            SocketProtocol::Default,
+            None::<&glib::Object>,
+        ) {
+            return show_error(format!("Failed to listen on IPC socket {path:?}: {e}"));
+        }
+
+        info!("Listening on IPC socket {path:?}");
+
+        let g = self.clone();
+        let l = listener.clone();
+        glib::spawn_future_local(async move {
+            loop {
+                match l.accept_future().await {
+                    Ok((conn, _)) => {
+                        glib::spawn_future_local(g.clone().ipc_connection(conn));
+                    }
+                    // Also happens when the listener is closed during shutdown
+                    Err(e) => return debug!("Stopped accepting IPC connections: {e}"),
+                }
+            }
+        });
+
+        self.ipc.replace(Some(IpcServer { listener, path }));
+    }
+
+    pub(super) fn ipc_close(&self) {
+        let Some(server) = self.ipc.take() else {
+            return;
+        };
+
+        server.listener.close();
+        if let Err(e) = remove_file(&server.path) {
+            error!("Failed to remove IPC socket {:?}: {e}", server.path);
+        }
+    }
+
+    async fn ipc_connection(self: Rc<Self>, conn: SocketConnection) {
+        let input = DataInputStream::new(&conn.input_stream());
+        let output = conn.output_stream();
+
+        loop {
+            let line = match input.read_line_utf8_future(glib::Priority::DEFAULT).await {
+                Ok(Some(line)) => line,
+                Ok(None) => return,
+                Err(e) => return warn!("Failed to read IPC request: {e}"),
+            };
+
+            let target = self.tabs.borrow().active_action_target();
+            let mut response = self.handle_request(&line, target).to_string();
+            response.push('\n');
+
+            if let Err((_, e)) =
+                output.write_all_future(response.into_bytes(), glib::Priority::DEFAULT).await
+            {
+                return warn!("Failed to write IPC response: {e}");
+            }
+        }
+    }
+
+    // Shared by the IPC socket and interactive scripts.
+    pub(super) fn handle_request(
+        self: &Rc<Self>,
+        line: &str,
+        default_target: ActionTarget,
+    ) -> Value {
+        let line = line.trim_end_matches('\r');
+        debug!("Got request: {line}");
+
+        let request = if line.trim_start().starts_with('{') {
+            match serde_json::from_str::<Request>(line) {
+                Ok(r) => r,
+                Err(e) => return json!({ "ok": false, "error": format!("Invalid request: {e}") }),
+            }
+        } else {
+            Request::Command { command: line.to_owned(), tab: None }
+        };
+
+        let target = |tab: Option<u64>| match tab {
+            Some(n) => ActionTarget::Tab(TabId::from_raw(n)),
+            None => default_target,
+        };
+
+        match request {
+            Request::Command { command, tab } => {
+                let target = target(tab);
+                let before = self.tabs.borrow().tab_ids();
+
+                if let Err(e) = self.run_command_checked(target, &command) {
+                    return json!({ "ok": false, "error": e });
+                }
+
+                // A newly opened tab, or wherever the target tab ended up
+                match self.tabs.borrow().ipc_result_tab(target, &before) {
+                    Some(tab) => json!({ "ok": true, "tab": tab }),
+                    None => json!({ "ok": true }),
+                }
+            }
+            Request::Query { query, tab } => match &*query {
+                "tabs" => json!({ "ok": true, "result": self.tabs.borrow().ipc_tabs() }),
+                "active" => json!({ "ok": true, "result": self.tabs.borrow().ipc_active() }),
+                "selection" => {
+                    let selection = self.tabs.borrow().ipc_selection(target(tab));
+                    json!({ "ok": true, "result": selection })
+                }
+                "operations" => {
+                    let ops: Vec<_> =
+                        self.ongoing_operations.borrow().iter().map(|op| op.ipc_status()).collect();
+                    json!({ "ok": true, "result": ops })
+                }
+                _ => json!({ "ok": false, "error": format!("Unknown query {query:?}") }),
+            },
+        }
+    }
+}
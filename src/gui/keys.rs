@@ -0,0 +1,359 @@
+use std::rc::Rc;
+use std::time::Duration;
+
+use ahash::AHashMap;
+use gtk::gdk::{Key, ModifierType};
+use gtk::glib::{self, Propagation, SourceId};
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+
+use super::input::parse_modifiers;
+use super::{Gui, gui_run};
+use crate::config::{CONFIG, OPTIONS, Shortcut};
+
+// A single key press. Lock is always masked out and the key is always uppercase.
+pub(super) type Chord = (ModifierType, Key);
+
+pub(super) enum Lookup {
+    Action(&'static str),
+    // The keys so far are the start of at least one longer sequence
+    Prefix,
+    Unbound,
+}
+
+// Configured shortcuts. Those without a mode are active in every mode.
+#[derive(Debug, Default)]
+pub(super) struct Keymap {
+    global: AHashMap<Vec<Chord>, &'static str>,
+    modes: AHashMap<&'static str, AHashMap<Vec<Chord>, &'static str>>,
+}
+
+impl Keymap {
+    // Invalid shortcuts are skipped here and reported by warn_config_problems.
+    pub(super) fn parse() -> Self {
+        let mut keymap = Self::default();
+
+        let mut handle = |s: &'static Shortcut| {
+            let keys = match parse_sequence(s) {
+                Ok(keys) => keys,
+                Err(e) => {
+                    warn!("Skipping shortcut for {:?}: {e}", s.action);
+                    return;
+                }
+            };
+
+            let map = match &s.mode {
+                Some(mode) => keymap.modes.entry(mode.as_str()).or_default(),
+                None => &mut keymap.global,
+            };
+            map.insert(keys, &*s.action);
+        };
+
+        CONFIG.shortcuts.iter().for_each(&mut handle);
+
+        if OPTIONS.chooser_mode.is_some() {
+            CONFIG.chooser_shortcuts.iter().for_each(&mut handle);
+        }
+
+        keymap
+    }
+
+    // Shortcuts for the current mode take precedence over those without a mode.
+    fn get(&self, mode: Option<&str>, keys: &[Chord]) -> Lookup {
+        let maps = || mode.and_then(|m| self.modes.get(m)).into_iter().chain([&self.global]);
+
+        if let Some(action) = maps().find_map(|m| m.get(keys).copied()) {
+            return Lookup::Action(action);
+        }
+
+        if maps().any(|m| m.keys().any(|seq| seq.starts_with(keys))) {
+            Lookup::Prefix
+        } else {
+            Lookup::Unbound
+        }
+    }
+
+    // Only single keys without a mode, like custom action shortcuts.
+    pub(super) fn global(&self, chord: Chord) -> Option<&'static str> {
+        self.global.get(&[chord][..]).copied()
+    }
+
+    fn has_mode(&self, mode: &str) -> bool {
+        self.modes.contains_key(mode)
+    }
+}
+
+// Shortcuts are written as modifiers and a key name joined by "+", like Control+Shift+E.
+pub(super) fn parse_shortcut(s: &str) -> Result<Chord, String> {
+    let (mods, key) = s.rsplit_once('+').unwrap_or(("", s));
+
+    let Some(key) = Key::from_name(key.trim()) else {
+        return Err(format!("Could not decode key {key:?}"));
+    };
+
+    Ok((parse_modifiers(&Some(mods.to_string())), key.to_upper()))
+}
+
+// A sequence is written as space-separated keys, like "G H" or "Control+X K".
+// The modifiers field applies to the last key.
+pub(super) fn parse_sequence(s: &Shortcut) -> Result<Vec<Chord>, String> {
+    let mut keys = s.key.split_whitespace().map(parse_shortcut).collect::<Result<Vec<_>, _>>()?;
+
+    let Some(last) = keys.last_mut() else {
+        return Err("No key given".to_string());
+    };
+    last.0 |= parse_modifiers(&s.modifiers);
+
+    Ok(keys)
+}
+
+fn chord_name((mods, key): Chord) -> String {
+    let mut name = String::new();
+
+    for (m, n) in [
+        (ModifierType::CONTROL_MASK, "Control"),
+        (ModifierType::ALT_MASK, "Alt"),
+        (ModifierType::SHIFT_MASK, "Shift"),
+        (ModifierType::SUPER_MASK, "Super"),
+        (ModifierType::META_MASK, "Command"),
+    ] {
+        if mods.contains(m) {
+            name.push_str(n);
+            name.push('+');
+        }
+    }
+
+    match key.to_unicode() {
+        Some(c) if !c.is_whitespace() && !c.is_control() => name.push(c),
+        _ => name.push_str(key.name().as_deref().unwrap_or("?")),
+    }
+
+    name
+}
+
+pub(super) fn sequence_name(keys: &[Chord]) -> String {
+    keys.iter().map(|c| chord_name(*c)).collect::<Vec<_>>().join(" ")
+}
+
+#[derive(Debug, Default)]
+pub(super) struct KeyState {
+    mode: Option<String>,
+    pending: Vec<Chord>,
+    timeout: Option<SourceId>,
+}
+
+impl Gui {
+    pub(super) fn handle_key(self: &Rc<Self>, key: Key, mods: ModifierType) -> Propagation {
+        let mods = mods & !ModifierType::LOCK_MASK;
+
+        let mut state = self.keys.borrow_mut();
+        state.pending.push((mods, key.to_upper()));
+
+        let lookup = self.shortcuts.borrow().get(state.mode.as_deref(), &state.pending);
+        let was_pending = state.pending.len() > 1;
+
+        if matches!(lookup, Lookup::Prefix) {
+            if let Some(timeout) = state.timeout.take() {
+                timeout.remove();
+            }
+
+            if let Some(ms) = CONFIG.key_sequence_timeout {
+                let timeout = glib::timeout_add_local_once(Duration::from_millis(ms.get()), || {
+                    gui_run(|g| {
+                        // The source is already gone
+                        g.keys.borrow_mut().timeout = None;
+                        g.clear_pending_keys();
+                    });
+                });
+                state.timeout = Some(timeout);
+            }
+
+            drop(state);
+            self.update_key_indicator();
+            return Propagation::Stop;
+        }
+
+        drop(state);
+        self.clear_pending_keys();
+
+        let Lookup::Action(action) = lookup else {
+            // An unfinished sequence swallows the key that broke it
+            if was_pending {
+                return Propagation::Stop;
+            }
+
+            self.menu.get().unwrap().activate_shortcut(self, key, mods);
+            return Propagation::Proceed;
+        };
+
+        self.run_action_active(action);
+        Propagation::Proceed
+    }
+
+    // While in a mode or partway through a sequence, keys aren't used for seeking.
+    // Neither are keys that start a sequence, like the G in "G H".
+    pub(super) fn keys_captured(&self, key: Key, mods: ModifierType) -> bool {
+        let state = self.keys.borrow();
+        if state.mode.is_some() || !state.pending.is_empty() {
+            return true;
+        }
+
+        let chord = (mods & !ModifierType::LOCK_MASK, key.to_upper());
+        matches!(self.shortcuts.borrow().get(None, &[chord]), Lookup::Prefix)
+    }
+
+    pub(super) fn set_mode(self: &Rc<Self>, mode: Option<&str>) {
+        if let Some(m) = mode
+            && !self.shortcuts.borrow().has_mode(m)
+        {
+            let e = format!("No shortcuts are bound in mode {m:?}");
+            warn!("{e}");
+            return self.warning(&e);
+        }
+
+        info!("Switching to mode {mode:?}");
+        self.clear_pending_keys();
+        self.keys.borrow_mut().mode = mode.map(str::to_string);
+        self.update_key_indicator();
+    }
+
+    // Leaves any mode that no longer has shortcuts.
+    pub(super) fn reload_keymap(self: &Rc<Self>) {
+        self.shortcuts.replace(Keymap::parse());
+        self.clear_pending_keys();
+
+        let mode = self.keys.borrow().mode.clone();
+        if let Some(m) = mode
+            && !self.shortcuts.borrow().has_mode(&m)
+        {
+            self.set_mode(None);
+        } else {
+            self.update_key_indicator();
+        }
+    }
+
+    fn clear_pending_keys(&self) {
+        let mut state = self.keys.borrow_mut();
+        state.pending.clear();
+        if let Some(timeout) = state.timeout.take() {
+            timeout.remove();
+        }
+        drop(state);
+
+        self.update_key_indicator();
+    }
+
+    fn update_key_indicator(&self) {
+        let state = self.keys.borrow();
+
+        let mut parts = Vec::new();
+        if let Some(mode) = &state.mode {
+            parts.push(format!("-- {mode} --"));
+        }
+        if !state.pending.is_empty() {
+            parts.push(sequence_name(&state.pending));
+        }
+
+        let indicator = &self.window.imp().key_indicator;
+        indicator.set_text(&parts.join("  "));
+        indicator.set_visible(!parts.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ahash::AHashMap;
+    use gtk::gdk::{Key, ModifierType};
+
+    use super::{Chord, Keymap, Lookup, parse_sequence, parse_shortcut};
+    use crate::config::Shortcut;
+
+    const NONE: ModifierType = ModifierType::empty();
+    const CONTROL: ModifierType = ModifierType::CONTROL_MASK;
+
+    fn shortcut(key: &str, modifiers: Option<&str>) -> Shortcut {
+        Shortcut {
+            action: String::new(),
+            key: key.to_string(),
+            modifiers: modifiers.map(str::to_string),
+            mode: None,
+        }
+    }
+
+    fn keymap(global: &[(&[Chord], &'static str)], normal: &[(&[Chord], &'static str)]) -> Keymap {
+        let map = |bindings: &[(&[Chord], &'static str)]| -> AHashMap<_, _> {
+            bindings.iter().map(|(keys, action)| (keys.to_vec(), *action)).collect()
+        };
+
+        Keymap { global: map(global), modes: [("normal", map(normal))].into_iter().collect() }
+    }
+
+    fn action(lookup: Lookup) -> Option<&'static str> {
+        match lookup {
+            Lookup::Action(a) => Some(a),
+            Lookup::Prefix | Lookup::Unbound => None,
+        }
+    }
+
+    #[test]
+    fn shortcuts() {
+        assert_eq!(parse_shortcut("g").unwrap(), (NONE, Key::G));
+        assert_eq!(parse_shortcut("Control+x").unwrap(), (CONTROL, Key::X));
+        assert_eq!(
+            parse_shortcut("Control+Shift+E").unwrap(),
+            (CONTROL | ModifierType::SHIFT_MASK, Key::E)
+        );
+        assert_eq!(parse_shortcut("question").unwrap(), (NONE, Key::question));
+        assert!(parse_shortcut("NotAKey").is_err());
+        assert!(parse_shortcut("Control+").is_err());
+    }
+
+    #[test]
+    fn sequences() {
+        let g_h = [(NONE, Key::G), (NONE, Key::H)];
+        assert_eq!(parse_sequence(&shortcut("g h", None)).unwrap(), g_h);
+        assert_eq!(parse_sequence(&shortcut("Control+X  K", None)).unwrap(), [
+            (CONTROL, Key::X),
+            (NONE, Key::K)
+        ]);
+        // The modifiers field only applies to the last key
+        assert_eq!(parse_sequence(&shortcut("G H", Some("Control"))).unwrap(), [
+            (NONE, Key::G),
+            (CONTROL, Key::H)
+        ]);
+        assert!(parse_sequence(&shortcut(" ", None)).is_err());
+        assert!(parse_sequence(&shortcut("G NotAKey", None)).is_err());
+    }
+
+    #[test]
+    fn prefixes() {
+        let g = (NONE, Key::G);
+        let h = (NONE, Key::H);
+        let map = keymap(&[(&[g, h], "Home")], &[]);
+
+        assert!(matches!(map.get(None, &[g]), Lookup::Prefix));
+        assert_eq!(action(map.get(None, &[g, h])), Some("Home"));
+        assert!(matches!(map.get(None, &[h]), Lookup::Unbound));
+        assert!(matches!(map.get(None, &[g, g]), Lookup::Unbound));
+        assert!(matches!(map.get(None, &[g, h, h]), Lookup::Unbound));
+    }
+
+    #[test]
+    fn modes() {
+        let g = (NONE, Key::G);
+        let y = (NONE, Key::Y);
+        let map = keymap(&[(&[g], "Refresh"), (&[y], "Copy")], &[(&[g], "Home"), (&[y, y], "Cut")]);
+
+        // Mode shortcuts take precedence, global shortcuts still apply in a mode
+        assert_eq!(action(map.get(Some("normal"), &[g])), Some("Home"));
+        assert_eq!(action(map.get(None, &[g])), Some("Refresh"));
+        assert_eq!(action(map.get(Some("other"), &[g])), Some("Refresh"));
+
+        // A complete global shortcut runs before a longer sequence in the mode
+        assert_eq!(action(map.get(Some("normal"), &[y])), Some("Copy"));
+        assert!(matches!(map.get(None, &[y, y]), Lookup::Unbound));
+
+        assert_eq!(map.global(g), Some("Refresh"));
+        assert!(map.has_mode("normal"));
+        assert!(!map.has_mode("other"));
+    }
+}
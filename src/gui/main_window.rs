@@ -39,6 +39,12 @@ mod imp {
         #[template_child]
         pub toast: TemplateChild<gtk::Label>,
 
+        #[template_child]
+        pub key_indicator: TemplateChild<gtk::Label>,
+
+        #[template_child]
+        pub command_line: TemplateChild<gtk::Entry>,
+
         #[template_child]
         pub progress_trackers: TemplateChild<gtk::Box>,
 
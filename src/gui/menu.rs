@@ -1,24 +1,27 @@
 use std::borrow::Cow;
 use std::cell::{Cell, RefCell};
-use std::collections::hash_map;
+use std::collections::{VecDeque, hash_map};
 use std::ffi::OsStr;
 use std::fs::File;
-use std::io::Read;
+use std::io::{ErrorKind, Read};
 use std::os::unix::prelude::{OsStrExt, PermissionsExt};
 use std::path::Path;
 use std::rc::Rc;
+use std::str::FromStr;
 use std::sync::{Arc, LazyLock};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use ahash::AHashMap;
-use gtk::gio::{Menu, MenuItem, SimpleAction, SimpleActionGroup};
-use gtk::glib::{Variant, VariantTy};
+use gtk::gdk::{Key, ModifierType};
+use gtk::gio::{Cancellable, Icon, Menu, MenuItem, SimpleAction, SimpleActionGroup};
+use gtk::glib::{GString, Variant, VariantTy};
 use gtk::prelude::*;
-use gtk::{PopoverMenu, PositionType};
+use gtk::{AlertDialog, PopoverMenu, PositionType};
 use regex::bytes::Regex;
 
-use super::{ActionTarget, Gui, TabId};
-use crate::com::{DirSettings, Entry, EntryObject, ManagerAction};
+use super::keys::{Keymap, parse_shortcut};
+use super::{ActionTarget, Gui, TabId, show_warning};
+use crate::com::{DirSettings, Entry, EntryObject, ManagerAction, SelectionVia};
 use crate::config::{ACTIONS_DIR, CONFIG, ContextMenuEntry, ContextMenuGroup, Selection};
 use crate::gui::clipboard;
 
@@ -120,6 +123,14 @@ struct ActionSettings {
     selection: Selection,
     priority: i32,
     parse_output: bool,
+    interactive: bool,
+    selection_via: SelectionVia,
+    group: Option<ContextMenuGroup>,
+    icon: Option<String>,
+    shortcut: Option<(ModifierType, Key)>,
+    confirm: Option<String>,
+    terminal: bool,
+    timeout: Option<Duration>,
 }
 
 impl PartialEq for ActionSettings {
@@ -140,27 +151,29 @@ impl Ord for ActionSettings {
 }
 
 static SETTINGS_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
-    regex::Regex::new(
-        r"(name|directories|files|mimetypes|extensions|regex|selection|priority|parse_output)=(.*)$")
-            .unwrap()
+    regex::Regex::new(concat!(
+        r"(name|directories|files|mimetypes|extensions|regex|selection|priority|parse_output|",
+        r"interactive|selection_via|submenu|section|icon|shortcut|confirm|terminal|timeout)=(.*)$"
+    ))
+    .unwrap()
 });
 
 impl ActionSettings {
-    fn parse_script(path: &Path, read: impl Read) -> Option<Self> {
+    // Ok(None) if this isn't a custom action at all, errors are for broken settings blocks.
+    fn parse_script(path: &Path, read: impl Read) -> Result<Option<Self>, String> {
         // No more than 1MB
         let mut read = read.take(1024 * 1024);
 
         let mut contents = Vec::with_capacity(1024 * 1024);
         read.read_to_end(&mut contents)
-            .map_err(|e| error!("Failed to read custom action in {path:?}: {e}"))
-            .ok()?;
+            .map_err(|e| format!("Failed to read custom action in {path:?}: {e}"))?;
 
         let lossy = OsStr::from_bytes(&contents).to_string_lossy();
         let mut lines = lossy.lines();
 
         if !lines.any(|l| l[0..50.min(l.len())].contains("**aw-fm-settings-begin**")) {
             error!("Found no beginning of settings line in {path:?}");
-            return None;
+            return Ok(None);
         }
 
         let mut name = None;
@@ -172,9 +185,36 @@ impl ActionSettings {
         let mut selection = Selection::Any;
         let mut priority = 0;
         let mut parse_output = false;
+        let mut interactive = false;
+        let mut selection_via = SelectionVia::Env;
+        let mut group = None;
+        let mut icon = None;
+        let mut shortcut = None;
+        let mut confirm = None;
+        let mut terminal = false;
+        let mut timeout = None;
+
+        let invalid = |line: &str| format!("Invalid settings block in {path:?}: got \"{line}\"");
 
         for line in lines {
             if line.contains("**aw-fm-settings-end**") {
+                if interactive && selection_via == SelectionVia::Stdin0 {
+                    return Err(format!(
+                        "Invalid settings block in {path:?}: interactive=true can't be combined \
+                         with selection_via=stdin0"
+                    ));
+                }
+
+                // The terminal gets the output and stdin is never passed through
+                if terminal
+                    && (parse_output || interactive || selection_via == SelectionVia::Stdin0)
+                {
+                    return Err(format!(
+                        "Invalid settings block in {path:?}: terminal=true can't be combined with \
+                         parse_output, interactive, or selection_via=stdin0"
+                    ));
+                }
+
                 let s = Self {
                     name,
                     directories,
@@ -185,9 +225,17 @@ impl ActionSettings {
                     selection,
                     priority,
                     parse_output,
+                    interactive,
+                    selection_via,
+                    group,
+                    icon,
+                    shortcut,
+                    confirm,
+                    terminal,
+                    timeout,
                 };
                 debug!("Read script from {path:?}: {s:#?}");
-                return Some(s);
+                return Ok(Some(s));
             }
 
             let Some(cap) = SETTINGS_RE.captures(line) else {
@@ -201,16 +249,10 @@ impl ActionSettings {
             match &cap[1] {
                 "name" => name = Some(rest.into()),
                 "directories" => {
-                    directories = rest
-                        .parse::<bool>()
-                        .map_err(|_e| error!("Invalid settings block in {path:?}: got \"{line}\""))
-                        .ok()?
+                    directories = rest.parse::<bool>().map_err(|_e| invalid(line))?
                 }
                 "files" => {
-                    files = rest
-                        .parse::<bool>()
-                        .map_err(|_e| error!("Invalid settings block in {path:?}: got \"{line}\""))
-                        .ok()?
+                    files = rest.parse::<bool>().map_err(|_e| invalid(line))?
                 }
                 "mimetypes" => {
                     mimetypes =
@@ -218,34 +260,59 @@ impl ActionSettings {
                 }
                 "extensions" => extensions = Some(rest.split(';').map(str::to_string).collect()),
                 "regex" => {
-                    let re = Regex::new(rest)
-                        .map_err(|e| error!("Invalid regex in {path:?}: {e}"))
-                        .ok()?;
+                    let re =
+                        Regex::new(rest).map_err(|e| format!("Invalid regex in {path:?}: {e}"))?;
                     regex = Some(re);
                 }
                 "selection" => {
-                    selection = Selection::try_from(rest)
-                        .map_err(|_e| error!("Invalid settings block in {path:?}: got \"{line}\""))
-                        .ok()?
+                    selection = Selection::try_from(rest).map_err(|_e| invalid(line))?
                 }
                 "priority" => {
-                    priority = rest
-                        .parse::<i32>()
-                        .map_err(|_e| error!("Invalid settings block in {path:?}: got \"{line}\""))
-                        .ok()?;
+                    priority = rest.parse::<i32>().map_err(|_e| invalid(line))?;
                 }
                 "parse_output" => {
-                    parse_output = rest
-                        .parse::<bool>()
-                        .map_err(|_e| error!("Invalid settings block in {path:?}: got \"{line}\""))
-                        .ok()?
+                    parse_output = rest.parse::<bool>().map_err(|_e| invalid(line))?
+                }
+                "interactive" => {
+                    interactive = rest.parse::<bool>().map_err(|_e| invalid(line))?
+                }
+                "selection_via" => {
+                    selection_via = SelectionVia::from_str(rest).map_err(|_e| invalid(line))?
+                }
+                "submenu" | "section" => {
+                    if group.is_some() {
+                        return Err(format!(
+                            "Invalid settings block in {path:?}: only one submenu or section"
+                        ));
+                    }
+
+                    group = Some(if &cap[1] == "submenu" {
+                        ContextMenuGroup::Submenu(rest.into())
+                    } else {
+                        ContextMenuGroup::Section(rest.into())
+                    });
+                }
+                "icon" => icon = Some(rest.into()),
+                "confirm" => confirm = Some(rest.into()),
+                "terminal" => {
+                    terminal = rest.parse::<bool>().map_err(|_e| invalid(line))?
+                }
+                "timeout" => {
+                    let secs = rest.parse::<u64>().map_err(|_e| invalid(line))?;
+                    // Zero means no timeout
+                    timeout = (secs > 0).then(|| Duration::from_secs(secs));
+                }
+                "shortcut" => {
+                    shortcut = Some(
+                        parse_shortcut(rest)
+                            .map_err(|e| format!("Invalid shortcut in {path:?}: {e}"))?,
+                    );
                 }
                 _ => {}
             }
         }
 
-        error!("Found no end of settings line in {path:?}");
-        None
+        Err(format!("Found no end of settings line in {path:?}"))
     }
 
     fn for_action(name: String, selection: Selection) -> Self {
@@ -260,6 +327,14 @@ impl ActionSettings {
             priority: 0,
             // Not functional at all here
             parse_output: false,
+            interactive: false,
+            selection_via: SelectionVia::Env,
+            group: None,
+            icon: None,
+            shortcut: None,
+            confirm: None,
+            terminal: false,
+            timeout: None,
         };
         debug!("Constructed filterable action from context menu entry: {s:#?}");
         s
@@ -332,6 +407,19 @@ impl ActionSettings {
         false
     }
 
+    // The same checks as when preparing the context menu, for running from a shortcut.
+    fn accepts(&self, entries: &[EntryObject], dir: &Path) -> bool {
+        if self.rejects_count(entries.len()) {
+            false
+        } else if self.permissive() {
+            true
+        } else if entries.is_empty() {
+            self.accepts_parent_dir(dir)
+        } else {
+            entries.iter().all(|eo| !self.rejects(&eo.get()))
+        }
+    }
+
     // We only check this if there's no selection
     fn accepts_parent_dir(&self, path: &Path) -> bool {
         if !self.directories {
@@ -366,54 +454,65 @@ impl Ord for CustomAction {
 }
 
 impl CustomAction {
+    // Ok(None) for anything that isn't a custom action.
     fn create_script(
         path: Arc<Path>,
         g: &Rc<Gui>,
         group: &SimpleActionGroup,
         n: usize,
-    ) -> Option<Self> {
+    ) -> Result<Option<Self>, String> {
         if !path.exists() || !path.is_file() {
             error!("Failed to read custom action in {path:?}: not a regular file");
-            return None;
+            return Ok(None);
         }
 
-        let m = path
-            .metadata()
-            .map_err(|e| error!("Failed to read custom action in {path:?}: {e}"))
-            .ok()?
-            .permissions()
-            .mode();
+        let m = match path.metadata() {
+            Ok(m) => m.permissions().mode(),
+            Err(e) => return Err(format!("Failed to read custom action in {path:?}: {e}")),
+        };
 
         if m & 0o111 == 0 {
             error!("Failed to read custom action in {path:?}: not executable");
-            return None;
+            return Ok(None);
         }
 
         let read = File::open(&path)
-            .map_err(|e| error!("Failed to read custom action in {path:?}: {e}"))
-            .ok()?;
-
-        let settings = ActionSettings::parse_script(&path, read)?;
+            .map_err(|e| format!("Failed to read custom action in {path:?}: {e}"))?;
 
+        let Some(settings) = ActionSettings::parse_script(&path, read)? else {
+            return Ok(None);
+        };
 
         let action = SimpleAction::new(&format!("custom-{n}"), None);
         let g = g.clone();
         let p = path.clone();
+        let confirm = settings.confirm.clone();
         action.connect_activate(move |_a, _v| {
-            if settings.parse_output {
-                g.send_manager(ManagerAction::Script(
-                    p.clone(),
-                    g.menu.get().unwrap().action_target.get(),
-                    g.get_env(),
-                ))
+            let target = g.menu.get().unwrap().action_target.get();
+            let mut ctx = g.exec_context(target, settings.selection_via);
+            ctx.terminal = settings.terminal;
+            ctx.timeout = settings.timeout;
+
+            let action = if settings.interactive {
+                ManagerAction::InteractiveScript(p.clone(), target, ctx)
+            } else if settings.parse_output {
+                ManagerAction::Script(p.clone(), target, ctx)
             } else {
-                g.send_manager(ManagerAction::Execute(p.clone(), g.get_env()))
+                ManagerAction::Execute(p.clone(), ctx)
+            };
+
+            match &confirm {
+                Some(message) => {
+                    let files = g.tabs.borrow().target_selection(target);
+                    confirm_action(&g, message, files, action);
+                }
+                None => g.send_manager(action),
             }
         });
 
         group.add_action(&action);
 
-        Some(Self { path: Some(path), settings, action })
+        Ok(Some(Self { path: Some(path), settings, action }))
     }
 
     fn create_action(
@@ -429,7 +528,7 @@ impl CustomAction {
         let g = g.clone();
         let cmd = context.action.clone();
         action.connect_activate(move |_a, _v| {
-            g.run_command(g.menu.get().unwrap().action_target.get(), &cmd);
+            g.run_action(g.menu.get().unwrap().action_target.get(), &cmd);
         });
 
         group.add_action(&action);
@@ -456,10 +555,139 @@ impl CustomAction {
         );
         menuitem.set_attribute_value("hidden-when", Some(&"action-disabled".to_variant()));
 
+        if let Some(icon) = &self.settings.icon {
+            match Icon::for_string(icon) {
+                Ok(icon) => menuitem.set_icon(&icon),
+                Err(e) => error!("Invalid icon {icon:?} for {}: {e}", self.display_name()),
+            }
+        }
+
         menuitem
     }
 }
 
+// Only this many selected files are listed, so the dialog doesn't grow off the screen.
+const CONFIRM_LIST_LIMIT: usize = 20;
+
+// Asks before running the action, listing the selection it will run against.
+fn confirm_action(g: &Rc<Gui>, message: &str, files: VecDeque<Arc<Path>>, action: ManagerAction) {
+    let mut detail: Vec<_> =
+        files.iter().take(CONFIRM_LIST_LIMIT).map(|p| p.to_string_lossy()).collect();
+    if files.len() > CONFIRM_LIST_LIMIT {
+        detail.push(format!("and {} more", files.len() - CONFIRM_LIST_LIMIT).into());
+    }
+
+    let alert = AlertDialog::builder()
+        .buttons(["Cancel", "Run"])
+        .cancel_button(0)
+        .default_button(1)
+        .message(message)
+        .detail(detail.join("\n"))
+        .build();
+
+    let gui = g.clone();
+    alert.choose(Some(&g.window), Cancellable::NONE, move |button| {
+        if button == Ok(1) {
+            gui.send_manager(action);
+        }
+    });
+}
+
+// For --check-config, reports the same problems as loading the actions directory.
+pub(super) fn check_actions() -> Vec<String> {
+    let rd = match ACTIONS_DIR.read_dir() {
+        Ok(rd) => rd,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Vec::new(),
+        Err(e) => return vec![format!("Failed to read custom actions directory: {e}")],
+    };
+
+    let mut paths: Vec<_> = rd.filter_map(Result::ok).map(|de| de.path()).collect();
+    paths.retain(|p| p.is_file());
+    paths.sort();
+
+    let configured = Keymap::parse();
+    let mut shortcuts = AHashMap::new();
+    let mut problems = Vec::new();
+
+    for path in paths {
+        let read = match File::open(&path) {
+            Ok(read) => read,
+            Err(e) => {
+                problems.push(format!("Failed to read custom action in {path:?}: {e}"));
+                continue;
+            }
+        };
+
+        let settings = match ActionSettings::parse_script(&path, read) {
+            Ok(Some(settings)) => settings,
+            Ok(None) => continue,
+            Err(e) => {
+                problems.push(e);
+                continue;
+            }
+        };
+
+        if !path.metadata().is_ok_and(|m| m.permissions().mode() & 0o111 != 0) {
+            problems.push(format!("Custom action {path:?} is not executable"));
+        }
+
+        let Some(shortcut) = settings.shortcut else {
+            continue;
+        };
+
+        if let Some(existing) = configured.global(shortcut) {
+            problems.push(format!(
+                "Shortcut for {path:?} conflicts with configured shortcut {existing:?}"
+            ));
+            continue;
+        }
+
+        match shortcuts.entry(shortcut) {
+            hash_map::Entry::Occupied(e) => {
+                problems.push(format!("Shortcut for {path:?} conflicts with {:?}", e.get()));
+            }
+            hash_map::Entry::Vacant(e) => {
+                e.insert(path);
+            }
+        }
+    }
+
+    problems
+}
+
+// Submenus and sections are created the first time they're used and shared by name.
+#[derive(Default)]
+struct MenuGroups {
+    submenus: AHashMap<String, Menu>,
+    sections: AHashMap<String, Menu>,
+}
+
+impl MenuGroups {
+    fn get(&mut self, root: &Menu, group: Option<&ContextMenuGroup>) -> Menu {
+        match group {
+            Some(ContextMenuGroup::Submenu(sm)) => self
+                .submenus
+                .entry(sm.clone())
+                .or_insert_with(|| {
+                    let submenu = Menu::new();
+                    root.append_submenu(Some(sm), &submenu);
+                    submenu
+                })
+                .clone(),
+            Some(ContextMenuGroup::Section(sc)) => self
+                .sections
+                .entry(sc.clone())
+                .or_insert_with(|| {
+                    let section = Menu::new();
+                    root.append_section(Some(sc), &section);
+                    section
+                })
+                .clone(),
+            None => root.clone(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(super) struct GuiMenu {
     // Checkboxes
@@ -477,7 +705,11 @@ pub(super) struct GuiMenu {
     menu: Menu,
     popover: PopoverMenu,
     custom: RefCell<Vec<CustomAction>>,
+    // Custom actions are reloaded individually, so their action names are never reused
+    next_custom: Cell<usize>,
     custom_context: RefCell<Vec<CustomAction>>,
+    // Indices into custom
+    shortcuts: RefCell<AHashMap<(ModifierType, Key), usize>>,
     action_target: Cell<ActionTarget>,
 }
 
@@ -496,7 +728,7 @@ impl GuiMenu {
         let g = gui.clone();
         command.connect_activate(move |_a, v| {
             let action = v.unwrap().str().unwrap();
-            g.run_command(g.menu.get().unwrap().action_target.get(), action);
+            g.run_action(g.menu.get().unwrap().action_target.get(), action);
         });
 
         let action_group = SimpleActionGroup::new();
@@ -536,7 +768,9 @@ impl GuiMenu {
             menu,
             popover,
             custom: RefCell::default(),
+            next_custom: Cell::default(),
             custom_context: RefCell::default(),
+            shortcuts: RefCell::default(),
             action_target: Cell::new(ActionTarget::Active),
         };
 
@@ -546,61 +780,209 @@ impl GuiMenu {
     }
 
     pub(super) fn rebuild_menu(&self, gui: &Rc<Gui>) {
+        self.remove_actions(&self.custom.take());
+
+        let paths = match ACTIONS_DIR.read_dir() {
+            Ok(rd) => rd,
+            Err(e) => {
+                warn!("Failed to read custom actions directory: {e}");
+                return self.install_custom_actions(gui, Vec::new(), Vec::new());
+            }
+        };
+
+        let paths = paths.filter_map(|r| match r {
+            Ok(de) => {
+                let p = de.path();
+                if !p.is_dir() { Some(Arc::<Path>::from(p)) } else { None }
+            }
+            Err(e) => {
+                error!("Failed to read custom actions directory: {e}");
+                None
+            }
+        });
+
+        let (custom, errors) = self.parse_custom_actions(gui, paths);
+        self.install_custom_actions(gui, custom, errors);
+    }
+
+    // Only re-parses the scripts that changed, the rest are kept as they are.
+    pub(super) fn reload_actions(&self, gui: &Rc<Gui>, changed: &[Arc<Path>]) {
+        let (stale, kept): (Vec<_>, Vec<_>) = self
+            .custom
+            .take()
+            .into_iter()
+            .partition(|ca| ca.path.as_ref().is_some_and(|p| changed.contains(p)));
+        self.remove_actions(&stale);
+
+        // Deleted scripts are just dropped
+        let paths = changed.iter().filter(|p| p.is_file()).cloned();
+        let (mut custom, errors) = self.parse_custom_actions(gui, paths);
+        custom.extend(kept);
+
+        info!("Reloaded {} changed custom action(s)", changed.len());
+        self.install_custom_actions(gui, custom, errors);
+    }
+
+    fn remove_actions(&self, actions: &[CustomAction]) {
+        for ca in actions {
+            self.action_group.remove_action(&ca.action.name());
+        }
+    }
+
+    fn install_custom_actions(
+        &self,
+        gui: &Rc<Gui>,
+        mut custom: Vec<CustomAction>,
+        errors: Vec<String>,
+    ) {
         if self.popover.is_visible() {
             self.popover.popdown();
         }
         self.menu.remove_all();
 
-        let custom = self.parse_custom_actions(gui);
+        custom.sort();
 
         self.build_menu(gui, &custom);
+        self.bind_shortcuts(gui, &custom);
 
         self.custom.replace(custom);
+
+        // So broken settings blocks are noticed right away
+        if !errors.is_empty() {
+            let msg = errors.join("\n");
+            error!("{msg}");
+            show_warning(msg);
+        }
     }
 
-    fn parse_custom_actions(&self, g: &Rc<Gui>) -> Vec<CustomAction> {
-        let iter = match ACTIONS_DIR.read_dir() {
-            Ok(rd) => rd,
-            Err(e) => {
-                warn!("Failed to read custom actions directory: {e}");
-                return Vec::new();
+    // Configured shortcuts take precedence over custom actions.
+    fn bind_shortcuts(&self, gui: &Gui, actions: &[CustomAction]) {
+        let mut shortcuts = AHashMap::new();
+        let mut conflicts = Vec::new();
+
+        for (i, ca) in actions.iter().enumerate() {
+            let Some(shortcut) = ca.settings.shortcut else {
+                continue;
+            };
+
+            if let Some(existing) = gui.shortcuts.borrow().global(shortcut) {
+                conflicts.push(format!(
+                    "Shortcut for {} conflicts with configured shortcut {existing:?}",
+                    ca.display_name()
+                ));
+                continue;
             }
-        };
 
-        // For now this doesn't seem to be worth parallelizing and dealing with !Send GTK types.
-        // ~1.5ms total with cold caches and a reasonable number of actions
-        let mut actions: Vec<_> = iter
-            .filter_map(|r| match r {
-                Ok(de) => {
-                    let p = de.path();
-                    if !p.is_dir() { Some(p) } else { None }
+            match shortcuts.entry(shortcut) {
+                hash_map::Entry::Occupied(e) => conflicts.push(format!(
+                    "Shortcut for {} conflicts with {}",
+                    ca.display_name(),
+                    actions[*e.get()].display_name()
+                )),
+                hash_map::Entry::Vacant(e) => {
+                    e.insert(i);
                 }
-                Err(e) => {
-                    error!("Failed to read custom actions directory: {e}");
-                    None
-                }
-            })
-            .enumerate()
-            .filter_map(|(n, f)| CustomAction::create_script(f.into(), g, &self.action_group, n))
-            .collect();
+            }
+        }
+
+        if !conflicts.is_empty() {
+            let msg = conflicts.join("\n");
+            error!("{msg}");
+            show_warning(msg);
+        }
+
+        self.shortcuts.replace(shortcuts);
+    }
+
+    // Returns false if no custom action is bound to this key.
+    pub(super) fn activate_shortcut(&self, g: &Gui, key: Key, mods: ModifierType) -> bool {
+        let mods = mods & !ModifierType::LOCK_MASK;
+        let Some(&i) = self.shortcuts.borrow().get(&(mods, key.to_upper())) else {
+            return false;
+        };
+
+        self.activate(g, i);
+        true
+    }
+
+    // Custom actions that apply to the active tab, by display name and action name.
+    pub(super) fn applicable_actions(&self, g: &Gui) -> Vec<(String, GString)> {
+        let Some((_tab, entries, dir)) = g.tabs.borrow().active_context() else {
+            return Vec::new();
+        };
+
+        self.custom
+            .borrow()
+            .iter()
+            .filter(|ca| ca.settings.accepts(&entries, &dir))
+            .map(|ca| (ca.display_name().into_owned(), ca.action.name()))
+            .collect()
+    }
+
+    // The actions can be reloaded between listing and activating them.
+    pub(super) fn activate_by_name(&self, g: &Gui, name: &str) {
+        let i = self.custom.borrow().iter().position(|ca| ca.action.name() == name);
+        match i {
+            Some(i) => self.activate(g, i),
+            None => show_warning("The custom action no longer exists"),
+        }
+    }
+
+    fn activate(&self, g: &Gui, i: usize) {
+        let Some((tab, entries, dir)) = g.tabs.borrow().active_context() else {
+            return;
+        };
+
+        let custom = self.custom.borrow();
+        let ca = &custom[i];
+        if !ca.settings.accepts(&entries, &dir) {
+            show_warning(format!("{} does not apply to the current selection", ca.display_name()));
+            return;
+        }
+
+        self.action_target.set(ActionTarget::Tab(tab));
+        ca.action.set_enabled(true);
+        ca.action.activate(None);
+    }
 
-        actions.sort();
-        actions
+    // For now this doesn't seem to be worth parallelizing and dealing with !Send GTK types.
+    // ~1.5ms total with cold caches and a reasonable number of actions
+    fn parse_custom_actions(
+        &self,
+        g: &Rc<Gui>,
+        paths: impl Iterator<Item = Arc<Path>>,
+    ) -> (Vec<CustomAction>, Vec<String>) {
+        let mut actions = Vec::new();
+        let mut errors = Vec::new();
+
+        for path in paths {
+            let n = self.next_custom.get();
+            self.next_custom.set(n + 1);
+
+            match CustomAction::create_script(path, g, &self.action_group, n) {
+                Ok(Some(ca)) => actions.push(ca),
+                Ok(None) => {}
+                Err(e) => errors.push(e),
+            }
+        }
+
+        (actions, errors)
     }
 
     fn build_menu(&self, gui: &Rc<Gui>, actions: &[CustomAction]) {
         let mut custom = actions.iter().fuse().peekable();
 
+        let mut groups = MenuGroups::default();
+
         while let Some(peeked) = custom.peek() {
             if peeked.settings.priority >= 0 {
                 break;
             }
 
-            self.menu.append_item(&custom.next().unwrap().menuitem())
+            let ca = custom.next().unwrap();
+            groups.get(&self.menu, ca.settings.group.as_ref()).append_item(&ca.menuitem());
         }
 
-        let mut submenus = AHashMap::new();
-        let mut sections = AHashMap::new();
         let mut filterable_entries = Vec::new();
 
         for c_entry in &CONFIG.context_menu {
@@ -634,31 +1016,11 @@ impl GuiMenu {
                 menuitem
             };
 
-            let menu = match &c_entry.group {
-                Some(ContextMenuGroup::Submenu(sm)) => match submenus.entry(sm.clone()) {
-                    hash_map::Entry::Occupied(e) => e.into_mut(),
-                    hash_map::Entry::Vacant(e) => {
-                        let submenu = Menu::new();
-                        self.menu.append_submenu(Some(sm), &submenu);
-                        e.insert(submenu)
-                    }
-                },
-                Some(ContextMenuGroup::Section(sc)) => match sections.entry(sc.clone()) {
-                    hash_map::Entry::Occupied(e) => e.into_mut(),
-                    hash_map::Entry::Vacant(e) => {
-                        let section = Menu::new();
-                        self.menu.append_section(Some(sc), &section);
-                        e.insert(section)
-                    }
-                },
-                None => &self.menu,
-            };
-
-            menu.append_item(&menuitem);
+            groups.get(&self.menu, c_entry.group.as_ref()).append_item(&menuitem);
         }
 
-        for a in custom {
-            self.menu.append_item(&a.menuitem());
+        for ca in custom {
+            groups.get(&self.menu, ca.settings.group.as_ref()).append_item(&ca.menuitem());
         }
 
 
@@ -770,3 +1132,56 @@ impl GuiMenu {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use gtk::gdk::{Key, ModifierType};
+
+    use super::ActionSettings;
+    use crate::config::ContextMenuGroup;
+
+    fn parse(settings: &[&str]) -> Result<Option<ActionSettings>, String> {
+        let script = format!(
+            "#!/bin/sh\n# **aw-fm-settings-begin**\n{}\n# **aw-fm-settings-end**\necho hi\n",
+            settings.iter().map(|s| format!("# {s}")).collect::<Vec<_>>().join("\n")
+        );
+        ActionSettings::parse_script(Path::new("test.sh"), script.as_bytes())
+    }
+
+    #[test]
+    fn menu_placement() {
+        let s = parse(&["name=Play", "submenu=Media", "icon=video-x-generic"]).unwrap().unwrap();
+        assert_eq!(s.name.as_deref(), Some("Play"));
+        assert!(matches!(&s.group, Some(ContextMenuGroup::Submenu(m)) if m == "Media"));
+        assert_eq!(s.icon.as_deref(), Some("video-x-generic"));
+        assert_eq!(s.shortcut, None);
+
+        let s = parse(&["section=Tools"]).unwrap().unwrap();
+        assert!(matches!(&s.group, Some(ContextMenuGroup::Section(m)) if m == "Tools"));
+
+        assert!(parse(&["submenu=Media", "section=Tools"]).is_err());
+    }
+
+    #[test]
+    fn shortcuts() {
+        let s = parse(&["shortcut=Control+Shift+m"]).unwrap().unwrap();
+        let mods = ModifierType::CONTROL_MASK | ModifierType::SHIFT_MASK;
+        assert_eq!(s.shortcut, Some((mods, Key::M)));
+
+        let e = parse(&["shortcut=Control+NotAKey"]).unwrap_err();
+        assert!(e.contains("Invalid shortcut"), "{e}");
+    }
+
+    #[test]
+    fn settings_blocks() {
+        let path = Path::new("test.sh");
+
+        let plain = "#!/bin/sh\necho hi\n";
+        assert!(ActionSettings::parse_script(path, plain.as_bytes()).unwrap().is_none());
+
+        let unterminated = "#!/bin/sh\n# **aw-fm-settings-begin**\n# name=Play\n";
+        assert!(ActionSettings::parse_script(path, unterminated.as_bytes()).is_err());
+    }
+}
@@ -2,6 +2,7 @@ use std::cell::{Cell, OnceCell, RefCell};
 use std::collections::VecDeque;
 use std::path::Path;
 use std::rc::Rc;
+use std::sync::Arc;
 use std::time::Duration;
 use std::vec::Vec;
 
@@ -13,7 +14,7 @@ use gtk::glib::{ControlFlow, ExitCode, SourceId, WeakRef, timeout_future_seconds
 use gtk::pango::{AttrInt, AttrList};
 use gtk::prelude::*;
 use gtk::subclass::prelude::ObjectSubclassIsExt;
-use gtk::{Bitset, SelectionModel, gdk, gio, glib};
+use gtk::{Bitset, SelectionModel, gio, glib};
 use path_clean::PathClean;
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 #[cfg(feature = "x11")]
@@ -31,7 +32,7 @@ use self::thumbnailer::Thumbnailer;
 use super::com::*;
 use crate::closing::{self, close};
 use crate::config::{CONFIG, DIALOG_RES, OPTIONS};
-use crate::database::DBCon;
+use crate::database::{DBCon, SavedSearch};
 use crate::gui::tabs::list::TabPosition;
 use crate::state_cache::{STATE, State, save_settings};
 
@@ -40,13 +41,18 @@ mod chooser;
 mod clipboard;
 mod dbus;
 mod input;
+mod ipc;
+mod keys;
 mod main_window;
 mod menu;
 mod operations;
 mod properties;
+mod scripts;
+mod session;
 mod tabs;
 mod thumbnailer;
 
+pub use input::check_config;
 pub use tabs::id::TabId;
 
 // The Rc<> ends up more ergonomic in most cases but it's too much of a pain to pass things into
@@ -124,17 +130,33 @@ struct Gui {
     tabs: RefCell<TabsList>,
 
     database: DBCon,
+    saved_searches: RefCell<Vec<SavedSearch>>,
+    // Visited directories with their frecency scores, highest first.
+    recent_dirs: RefCell<Arc<[(Arc<Path>, f64)]>>,
+    // A refresh is already queued
+    recent_dirs_stale: Cell<bool>,
     thumbnailer: Thumbnailer,
 
     open_dialogs: RefCell<input::OpenDialogs>,
     chooser: RefCell<Option<chooser::Chooser>>,
-    shortcuts: AHashMap<ModifierType, AHashMap<gdk::Key, &'static str>>,
-    mouse_actions: AHashMap<ModifierType, AHashMap<u32, &'static str>>,
+    shortcuts: RefCell<keys::Keymap>,
+    keys: RefCell<keys::KeyState>,
+    command_line: RefCell<input::CommandLine>,
+    mouse_actions: RefCell<AHashMap<ModifierType, AHashMap<u32, &'static str>>>,
+    css: gtk::CssProvider,
 
     ongoing_operations: RefCell<Vec<Rc<Operation>>>,
     finished_operations: RefCell<VecDeque<Rc<Operation>>>,
+    scripts: RefCell<scripts::Scripts>,
 
     dbus_owner: DebugIgnore<Cell<Option<OwnerId>>>,
+    ipc: RefCell<Option<ipc::IpcServer>>,
+    // Set while running a command on behalf of something that wants to know whether it failed.
+    command_errors: RefCell<Option<Vec<String>>>,
+    // Set while collecting warnings to show as one, so they don't replace each other.
+    warning_batch: RefCell<Option<Vec<String>>>,
+    // Only set once any pending session restore has been resolved.
+    autosave: Cell<bool>,
 
     manager_sender: UnboundedSender<ManagerAction>,
 
@@ -237,22 +259,14 @@ impl Gui {
             }
         }
 
-        let provider = gtk::CssProvider::new();
-        let style = include_str!("style.css");
-        if let Some(bg) = CONFIG.background_colour {
-            window.remove_css_class("background");
-            window.imp().overlay.add_css_class("main-nobg");
-
-            provider.load_from_string(&format!("{style}\n window.main {{ background: {bg}; }}"));
-        } else {
-            provider.load_from_string(style);
-        }
+        let css = gtk::CssProvider::new();
+        load_css(&window, &css);
 
         // We give the CssProvider to the default screen so the CSS rules we added
         // can be applied to our window.
         gtk::style_context_add_provider_for_display(
             &WidgetExt::display(&window),
-            &provider,
+            &css,
             gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
         );
 
@@ -270,6 +284,13 @@ impl Gui {
 
         let tabs = TabsList::new(&window);
 
+        let database = DBCon::connect();
+        let saved_searches = if OPTIONS.chooser_mode.is_none() {
+            database.saved_searches()
+        } else {
+            Vec::new()
+        };
+
         let rc = Rc::new(Self {
             window,
 
@@ -278,18 +299,29 @@ impl Gui {
 
             tabs: tabs.into(),
 
-            database: DBCon::connect(),
+            database,
+            saved_searches: saved_searches.into(),
+            recent_dirs: RefCell::default(),
+            recent_dirs_stale: Cell::default(),
             thumbnailer: Thumbnailer::new(),
 
             open_dialogs: RefCell::default(),
             chooser: RefCell::default(),
-            shortcuts: Self::parse_shortcuts(),
-            mouse_actions: Self::parse_mouse_actions(),
+            shortcuts: keys::Keymap::parse().into(),
+            keys: RefCell::default(),
+            command_line: RefCell::default(),
+            mouse_actions: Self::parse_mouse_actions().into(),
+            css,
 
             ongoing_operations: RefCell::default(),
             finished_operations: RefCell::default(),
+            scripts: RefCell::default(),
 
             dbus_owner: DebugIgnore::default(),
+            ipc: RefCell::default(),
+            command_errors: RefCell::default(),
+            warning_batch: RefCell::default(),
+            autosave: Cell::default(),
 
             manager_sender,
 
@@ -304,17 +336,21 @@ impl Gui {
         if OPTIONS.chooser_mode.is_none() {
             rc.dbus_register();
         }
-
-        rc.menu.set(menu::GuiMenu::new(&rc)).unwrap();
+        rc.ipc_listen();
+        rc.refresh_recent_dirs();
 
         let g = rc.clone();
         application.connect_shutdown(move |_a| {
             info!("Shutting down application");
 
+            if !closing::crashed() {
+                g.mark_clean_shutdown();
+            }
             g.database.destroy();
 
             g.tabs.borrow_mut().cancel_loads();
             g.dbus_unregister();
+            g.ipc_close();
             closing::close();
         });
 
@@ -338,7 +374,11 @@ impl Gui {
             g.window.close();
         });
 
-        rc.setup();
+        // Broken custom actions and config problems are shown together
+        rc.batch_warnings(|| {
+            rc.menu.set(menu::GuiMenu::new(&rc)).unwrap();
+            rc.setup();
+        });
 
         rc
     }
@@ -351,6 +391,7 @@ impl Gui {
         let g = self.clone();
         self.window.connect_close_request(move |w| {
             g.cancel_operations();
+            g.autosave_session();
 
             save_settings(State {
                 // Does not handle fullscreen state, probably fine
@@ -364,6 +405,8 @@ impl Gui {
 
         self.filechooser_finish_setup();
 
+        self.setup_session_restore();
+
         if !CONFIG.force_small_thumbnails {
             let g = self.clone();
             let check_dpi = move |surface: &Surface| {
@@ -416,7 +459,21 @@ impl Gui {
             }
             ConveyWarning(warning) => self.warning(warning),
             Action(action, target) => self.run_command(target, &action),
-            Completion(completed) => self.tabs.borrow_mut().handle_completion(completed),
+            InteractiveAction(action, target, respond) => {
+                self.interactive_action(&action, target, respond)
+            }
+            Completion(completed) => match completed.target {
+                CompletionTarget::Tab(id) => {
+                    self.tabs.borrow_mut().handle_completion(id, completed)
+                }
+                CompletionTarget::CommandLine => self.command_line_completed(completed),
+            },
+            CustomActionsChanged(paths) => self.menu.get().unwrap().reload_actions(self, &paths),
+            ConfigChanged => self.reload_config(),
+            ScriptStarted(id, name) => self.script_started(id, name),
+            ScriptOutput(id, line) => self.script_output(id, line),
+            ScriptProgress(id, fraction, message) => self.script_progress(id, fraction, &message),
+            ScriptFinished(id) => self.script_finished(id),
             Quit => {
                 self.window.close();
                 closing::close();
@@ -440,6 +497,15 @@ impl Gui {
 
     // Shows a warning that times out and doesn't need to be dismissed.
     fn warning(self: &Rc<Self>, msg: impl AsRef<str>) {
+        // command_errors gets the combined warning once the batch is shown
+        if let Some(batch) = &mut *self.warning_batch.borrow_mut() {
+            return batch.push(msg.as_ref().to_owned());
+        }
+
+        if let Some(errors) = &mut *self.command_errors.borrow_mut() {
+            errors.push(msg.as_ref().to_owned());
+        }
+
         let toast = &self.window.imp().toast;
         let last_warning = self.warning_timeout.take();
 
@@ -465,7 +531,22 @@ impl Gui {
         self.warning_timeout.set(Some(timeout));
     }
 
+    // Runs f and shows every warning from it as one.
+    fn batch_warnings(self: &Rc<Self>, f: impl FnOnce()) {
+        let outer = self.warning_batch.replace(Some(Vec::new()));
+        f();
+        let warnings = self.warning_batch.replace(outer).unwrap_or_default();
+
+        if !warnings.is_empty() {
+            self.warning(warnings.join("\n"));
+        }
+    }
+
     fn error(&self, msg: impl AsRef<str>) {
+        if let Some(errors) = &mut *self.command_errors.borrow_mut() {
+            errors.push(msg.as_ref().to_owned());
+        }
+
         if let Some(warning) = self.warning_timeout.take() {
             warning.remove();
         }
@@ -604,6 +685,22 @@ impl Selected<'_> {
     }
 }
 
+// Also called when reloading the config.
+fn load_css(window: &MainWindow, provider: &gtk::CssProvider) {
+    let style = include_str!("style.css");
+    if let Some(bg) = CONFIG.background_colour {
+        window.remove_css_class("background");
+        window.imp().overlay.add_css_class("main-nobg");
+
+        provider.load_from_string(&format!("{style}\n window.main {{ background: {bg}; }}"));
+    } else {
+        window.add_css_class("background");
+        window.imp().overlay.remove_css_class("main-nobg");
+
+        provider.load_from_string(style);
+    }
+}
+
 fn label_attributes(label: &gtk::Label) {
     PANGO_ATTRIBUTES.with(|pa| label.set_attributes(Some(pa)));
 }
@@ -22,6 +22,8 @@ mod ask;
 mod progress;
 mod undo;
 
+pub(super) use self::progress::Tracker;
+
 const OPERATIONS_HISTORY: usize = 10;
 
 static COPY_REGEX: LazyLock<Regex> =
@@ -69,6 +71,8 @@ pub enum Outcome {
     CopyOverwrite(Arc<Path>),
     // Undo -> delete if still 0 sized
     NewFile(Arc<Path>),
+    // Undo -> delete if still a symlink
+    Symlink(Arc<Path>),
     // FileInfo needs to be restored after we populate the contents, which is awkward.
     // Could unconditionally store FileInfo to restore it, probably not worth it.
     RemoveSourceDir(Arc<Path>, FileInfo),
@@ -90,6 +94,7 @@ impl Outcome {
             | Self::Copy(_)
             | Self::CopyOverwrite(_)
             | Self::NewFile(_)
+            | Self::Symlink(_)
             | Self::RemoveSourceDir(..)
             | Self::CreateDestDir(_) => true,
             Self::MergeDestDir(_) | Self::Skip | Self::Delete | Self::DeleteDir | Self::Trash => {
@@ -103,6 +108,8 @@ impl Outcome {
 pub enum Kind {
     Move(Arc<Path>),
     Copy(Arc<Path>),
+    // Creates symlinks in the directory pointing to the source files
+    Link(Arc<Path>),
     Rename(Arc<Path>),
 
     MakeDir(Arc<Path>),
@@ -131,6 +138,7 @@ impl Kind {
         match self {
             Self::Move(_) => "Move",
             Self::Copy(_) => "Copy",
+            Self::Link(_) => "Link",
             Self::Rename(_) => "Rename",
             Self::MakeDir(_) => "MakeDir",
             Self::MakeFile(_) => "MakeFile",
@@ -150,6 +158,7 @@ impl Kind {
         match s {
             Self::Move(d)
             | Self::Copy(d)
+            | Self::Link(d)
             | Self::Rename(d)
             | Self::Trash(d)
             | Self::Delete(d)
@@ -163,7 +172,8 @@ impl Kind {
         match self {
             Self::Move(_) => Fragment::Moved,
             Self::Copy(_) => Fragment::Copied,
-            Self::Rename(_)
+            Self::Link(_)
+            | Self::Rename(_)
             | Self::MakeDir(_)
             | Self::MakeFile(_)
             | Self::Undo { .. }
@@ -319,6 +329,12 @@ impl Operation {
                     return None;
                 }
             }
+            Kind::Link(_) => {
+                if source_files.is_empty() {
+                    warn!("Got empty file operation {}, ignoring.", kind.str());
+                    return None;
+                }
+            }
             Kind::MakeDir(_) | Kind::MakeFile(_) => {
                 if !source_files.is_empty() {
                     show_warning(format!(
@@ -350,6 +366,16 @@ impl Operation {
         Some(rc)
     }
 
+    pub fn ipc_status(&self) -> serde_json::Value {
+        let (finished, total) = self.progress.borrow().counts();
+        serde_json::json!({
+            "kind": self.kind.str(),
+            "directory": self.kind.dir().to_string_lossy(),
+            "finished": finished,
+            "total": total,
+        })
+    }
+
     pub fn outcomes(&self) -> Ref<'_, [Outcome]> {
         let p = self.progress.borrow();
         Ref::map(p, Progress::log)
@@ -365,6 +391,7 @@ impl Operation {
         let status = match &self.kind {
             Kind::Move(p) => self.process_next_move(p),
             Kind::Copy(p) => self.process_next_copy(p),
+            Kind::Link(p) => self.process_next_link(p),
             Kind::Rename(p) => self.process_rename(p),
             Kind::MakeDir(p) => self.process_make_dir(p),
             Kind::MakeFile(p) => self.process_make_file(p),
@@ -595,6 +622,41 @@ impl Operation {
         );
     }
 
+    // Links are cheap enough to create synchronously, one per call.
+    fn process_next_link(self: &Rc<Self>, dest: &Path) -> Status {
+        let mut progress = self.progress.borrow_mut();
+        let Some(src) = progress.pop_source() else {
+            return Status::Done;
+        };
+
+        let Some(name) = src.file_name() else {
+            error!("Tried to link file without filename");
+            return Status::CallAgain;
+        };
+        let dst: Arc<Path> = dest.join(name).into();
+
+        // Also catches broken symlinks
+        if dst.symlink_metadata().is_ok() {
+            show_warning(format!("Not linking {src:?}, {dst:?} already exists"));
+            progress.push_outcome(Outcome::Skip);
+            return Status::CallAgain;
+        }
+
+        match std::os::unix::fs::symlink(&src, &dst) {
+            Ok(_) => {
+                trace!("Linked {dst:?} to {src:?}");
+                progress.push_outcome(Outcome::Symlink(dst));
+                Status::CallAgain
+            }
+            Err(e) => {
+                drop(progress);
+                show_error(format!("Failed to link {src:?} to {dst:?}: {e}, aborting operation"));
+                self.cancel();
+                Status::Done
+            }
+        }
+    }
+
     fn prepare_copymove(self: &Rc<Self>, src: Arc<Path>, mut dst: Arc<Path>) -> CopyMovePrep {
         if src.is_dir() && !src.is_symlink() {
             return self.prepare_dest_dir(src, dst);
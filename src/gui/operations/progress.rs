@@ -115,6 +115,10 @@ impl Progress {
         self.collision_cache = AHashMap::new();
     }
 
+    pub const fn counts(&self) -> (usize, usize) {
+        (self.finished, self.total)
+    }
+
     pub fn log(&self) -> &[Outcome] {
         &self.log
     }
@@ -200,6 +204,7 @@ impl Progress {
             | Outcome::Copy(_)
             | Outcome::CopyOverwrite(_)
             | Outcome::NewFile(_)
+            | Outcome::Symlink(_)
             | Outcome::Trash
             | Outcome::CreateDestDir(_)
             | Outcome::MergeDestDir(_) // does this really count?
@@ -400,6 +405,36 @@ impl Tracker {
 
         s
     }
+
+    // For scripts reporting their own progress, the cancel button kills them.
+    pub fn for_script(name: &str, kill: impl Fn() + 'static) -> Self {
+        let s: Self = Object::new();
+
+        let imp = s.imp();
+        imp.title.set_text(name);
+        imp.cancel.connect_clicked(move |_| kill());
+
+        gui_run(|g| g.window.imp().progress_trackers.prepend(&s));
+
+        s
+    }
+
+    // Status messages leave the progress bar as it was.
+    pub fn set_progress(&self, fraction: Option<f64>, message: &str) {
+        let imp = self.imp();
+        imp.subtitle.set_text(message);
+
+        if let Some(fraction) = fraction {
+            imp.bar.set_fraction(fraction);
+            imp.bar.set_visible(true);
+        }
+    }
+
+    pub fn close(&self) {
+        if let Some(parent) = self.parent().and_downcast::<gtk::Box>() {
+            parent.remove(self);
+        }
+    }
 }
 
 
@@ -424,9 +459,13 @@ mod imp {
         #[template_child]
         pub current: TemplateChild<gtk::Label>,
 
+        #[template_child]
+        pub bar: TemplateChild<gtk::ProgressBar>,
+
         #[template_child]
         pub cancel: TemplateChild<gtk::Button>,
 
+        // Unset for scripts
         pub operation: OnceCell<Rc<Operation>>,
     }
 
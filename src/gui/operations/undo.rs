@@ -60,6 +60,15 @@ impl Operation {
                 self.do_delete(path, false);
                 Status::AsyncScheduled
             }
+            Outcome::Symlink(path) => {
+                if !path.is_symlink() {
+                    info!("{path:?} is no longer a symlink. Not removing.");
+                    return Status::CallAgain;
+                }
+
+                self.do_delete(path, false);
+                Status::AsyncScheduled
+            }
             Outcome::RemoveSourceDir(path, file_info) => {
                 if path.exists() {
                     info!("Not recreating {path:?} since it already exists");
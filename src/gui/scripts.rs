@@ -0,0 +1,262 @@
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use gtk::glib::{self, ControlFlow, Propagation, SourceId};
+use gtk::pango::WrapMode;
+use gtk::prelude::*;
+use gtk::{Align, Orientation};
+
+use super::input::wrap_in_box_with_close_button;
+use super::operations::Tracker;
+use super::{Gui, gui_run, label_attributes};
+use crate::com::ManagerAction;
+use crate::config::DIALOG_RES;
+
+const TAIL_LINES: usize = 8;
+// So a single huge line can't blow up the panel
+const MAX_LINE_CHARS: usize = 300;
+
+// A process being tracked by the manager, see manager/scripts.rs.
+#[derive(Debug)]
+struct Script {
+    id: u64,
+    name: String,
+    started: Instant,
+    tail: VecDeque<String>,
+    // Only while the panel is open
+    row: Option<Row>,
+    // Shown with file operations once the script reports progress
+    progress: Option<Tracker>,
+}
+
+#[derive(Debug)]
+struct Row {
+    root: gtk::ListBoxRow,
+    runtime: gtk::Label,
+    tail: gtk::Label,
+}
+
+#[derive(Debug)]
+struct Panel {
+    dialog: gtk::Window,
+    list: gtk::ListBox,
+    ticker: SourceId,
+}
+
+#[derive(Debug, Default)]
+pub(super) struct Scripts {
+    running: Vec<Script>,
+    panel: Option<Panel>,
+}
+
+impl Scripts {
+    pub(super) const fn panel_open(&self) -> bool {
+        self.panel.is_some()
+    }
+}
+
+impl Script {
+    fn tail_text(&self) -> String {
+        self.tail.iter().map(String::as_str).collect::<Vec<_>>().join("\n")
+    }
+}
+
+impl Panel {
+    fn add_row(&self, script: &Script) -> Row {
+        let header = gtk::Box::new(Orientation::Horizontal, 12);
+
+        let name = gtk::Label::new(Some(&script.name));
+        name.set_halign(Align::Start);
+        name.set_hexpand(true);
+        label_attributes(&name);
+
+        let runtime = gtk::Label::new(Some(&format_runtime(script.started.elapsed())));
+
+        let kill = gtk::Button::with_label("Kill");
+        let id = script.id;
+        kill.connect_clicked(move |_| gui_run(|g| g.send_manager(ManagerAction::KillScript(id))));
+
+        header.append(&name);
+        header.append(&runtime);
+        header.append(&kill);
+
+        let tail = gtk::Label::new(Some(&script.tail_text()));
+        tail.set_halign(Align::Start);
+        tail.set_xalign(0.0);
+        tail.set_wrap(true);
+        tail.set_wrap_mode(WrapMode::WordChar);
+        tail.set_selectable(true);
+        tail.add_css_class("monospace");
+        tail.set_visible(!script.tail.is_empty());
+
+        let vbox = gtk::Box::new(Orientation::Vertical, 4);
+        vbox.set_margin_start(8);
+        vbox.set_margin_end(8);
+        vbox.set_margin_top(4);
+        vbox.set_margin_bottom(4);
+        vbox.append(&header);
+        vbox.append(&tail);
+
+        let root = gtk::ListBoxRow::new();
+        root.set_activatable(false);
+        root.set_child(Some(&vbox));
+        self.list.append(&root);
+
+        Row { root, runtime, tail }
+    }
+}
+
+impl Gui {
+    pub(super) fn script_started(&self, id: u64, name: String) {
+        let mut scripts = self.scripts.borrow_mut();
+
+        let mut script = Script {
+            id,
+            name,
+            started: Instant::now(),
+            tail: VecDeque::new(),
+            row: None,
+            progress: None,
+        };
+        if let Some(panel) = &scripts.panel {
+            script.row = Some(panel.add_row(&script));
+        }
+
+        scripts.running.push(script);
+    }
+
+    pub(super) fn script_output(&self, id: u64, mut line: String) {
+        let mut scripts = self.scripts.borrow_mut();
+        // Output can arrive after the script has already finished
+        let Some(script) = scripts.running.iter_mut().find(|s| s.id == id) else {
+            return;
+        };
+
+        if let Some((i, _)) = line.char_indices().nth(MAX_LINE_CHARS) {
+            line.truncate(i);
+            line.push('…');
+        }
+
+        if script.tail.len() == TAIL_LINES {
+            script.tail.pop_front();
+        }
+        script.tail.push_back(line);
+
+        if let Some(row) = &script.row {
+            row.tail.set_text(&script.tail_text());
+            row.tail.set_visible(true);
+        }
+    }
+
+    pub(super) fn script_progress(&self, id: u64, fraction: Option<f64>, message: &str) {
+        let mut scripts = self.scripts.borrow_mut();
+        let Some(script) = scripts.running.iter_mut().find(|s| s.id == id) else {
+            return;
+        };
+
+        let progress = script.progress.get_or_insert_with(|| {
+            Tracker::for_script(&script.name, move || {
+                gui_run(|g| g.send_manager(ManagerAction::KillScript(id)))
+            })
+        });
+        progress.set_progress(fraction, message);
+    }
+
+    pub(super) fn script_finished(&self, id: u64) {
+        let mut scripts = self.scripts.borrow_mut();
+        let Some(i) = scripts.running.iter().position(|s| s.id == id) else {
+            return;
+        };
+
+        let script = scripts.running.remove(i);
+        if let Some(progress) = script.progress {
+            progress.close();
+        }
+
+        if let (Some(panel), Some(row)) = (&scripts.panel, script.row) {
+            panel.list.remove(&row.root);
+        }
+    }
+
+    pub(super) fn kill_scripts(&self) {
+        self.send_manager(ManagerAction::KillScripts);
+    }
+
+    fn update_runtimes(&self) {
+        for script in &self.scripts.borrow().running {
+            if let Some(row) = &script.row {
+                row.runtime.set_text(&format_runtime(script.started.elapsed()));
+            }
+        }
+    }
+
+    pub(super) fn scripts_panel(self: &Rc<Self>) {
+        if let Some(panel) = &self.scripts.borrow().panel {
+            info!("Scripts panel already open");
+            panel.dialog.present();
+            return;
+        }
+
+        let dialog =
+            gtk::Window::builder().title("Running Scripts").transient_for(&self.window).build();
+        let res = *DIALOG_RES;
+        dialog.set_default_width(res.0);
+        dialog.set_default_height(res.1 / 2);
+
+        self.close_on_quit_or_esc(&dialog);
+
+        let list = gtk::ListBox::new();
+        list.set_selection_mode(gtk::SelectionMode::None);
+        list.set_placeholder(Some(&gtk::Label::new(Some("No scripts are running"))));
+
+        let scrolled =
+            gtk::ScrolledWindow::builder().hscrollbar_policy(gtk::PolicyType::Never).build();
+        scrolled.set_vexpand(true);
+        scrolled.set_child(Some(&list));
+
+        let actions = wrap_in_box_with_close_button(&dialog, scrolled, "Close");
+
+        let kill_all = gtk::Button::with_label("Kill All");
+        kill_all.connect_clicked(move |_| gui_run(|g| g.kill_scripts()));
+        actions.append(&kill_all);
+
+        let ticker = glib::timeout_add_local(Duration::from_secs(1), || {
+            gui_run(|g| g.update_runtimes());
+            ControlFlow::Continue
+        });
+
+        let g = self.clone();
+        dialog.connect_close_request(move |d| {
+            let mut scripts = g.scripts.borrow_mut();
+            if let Some(panel) = scripts.panel.take() {
+                panel.ticker.remove();
+            }
+            scripts.running.iter_mut().for_each(|s| s.row = None);
+            drop(scripts);
+
+            d.destroy();
+            Propagation::Proceed
+        });
+
+        let panel = Panel { dialog: dialog.clone(), list, ticker };
+
+        let mut scripts = self.scripts.borrow_mut();
+        for script in &mut scripts.running {
+            script.row = Some(panel.add_row(script));
+        }
+        scripts.panel = Some(panel);
+        drop(scripts);
+
+        dialog.set_visible(true);
+    }
+}
+
+fn format_runtime(d: Duration) -> String {
+    let secs = d.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
@@ -0,0 +1,94 @@
+use std::rc::Rc;
+
+use gtk::AlertDialog;
+use gtk::gio::Cancellable;
+use gtk::glib::{self, ControlFlow};
+
+use super::Gui;
+use crate::config::{CONFIG, OPTIONS, RestoreSession};
+use crate::database::Session;
+
+// Reserved name for the automatically saved session.
+// It can still be loaded manually with LoadSession, but not saved over or deleted.
+pub(super) const LAST_SESSION: &str = "*last*";
+
+const AUTOSAVE_INTERVAL_SECONDS: u32 = 60;
+
+impl Gui {
+    pub(super) fn setup_session_restore(self: &Rc<Self>) {
+        if CONFIG.restore_session != RestoreSession::Last || OPTIONS.chooser_mode.is_some() {
+            return;
+        }
+
+        let state = self.database.mark_running();
+        if !state.primary {
+            // The last session belongs to the instance that was already running
+            info!("Another instance is running, not restoring or autosaving the last session");
+            return;
+        }
+
+        let g = self.clone();
+        glib::timeout_add_seconds_local(AUTOSAVE_INTERVAL_SECONDS, move || {
+            g.autosave_session();
+            ControlFlow::Continue
+        });
+
+        if OPTIONS.file_name.is_some() {
+            info!("Not restoring last session since a path was specified");
+            return self.autosave.set(true);
+        }
+
+        let Some(session) = self.database.load_session(LAST_SESSION.to_string()) else {
+            return self.autosave.set(true);
+        };
+
+        if !state.crashed {
+            info!("Restoring last session");
+            self.tabs.borrow_mut().load_session(session);
+            return self.autosave.set(true);
+        }
+
+        self.ask_restore_session(session);
+    }
+
+    // Don't overwrite the saved session while the user is deciding whether to restore it.
+    fn ask_restore_session(self: &Rc<Self>, session: Session) {
+        info!("Last session did not shut down cleanly, asking before restoring");
+
+        let alert = AlertDialog::builder()
+            .buttons(["Don't Restore", "Restore"])
+            .cancel_button(0)
+            .default_button(1)
+            .message("aw-fm did not shut down cleanly")
+            .detail("Restore the last session?")
+            .build();
+
+        let g = self.clone();
+        alert.choose(Some(&self.window), Cancellable::NONE, move |button| {
+            if button == Ok(1) {
+                info!("Restoring last session after unclean shutdown");
+                g.tabs.borrow_mut().load_session(session);
+            }
+            g.autosave.set(true);
+        });
+    }
+
+    pub(super) fn autosave_session(&self) {
+        if !self.autosave.get() {
+            return;
+        }
+
+        if let Some(session) = self.tabs.borrow().get_session() {
+            trace!("Autosaving session");
+            self.database.save_session(LAST_SESSION.to_string(), session);
+        }
+    }
+
+    pub(super) fn mark_clean_shutdown(&self) {
+        if CONFIG.restore_session != RestoreSession::Last || OPTIONS.chooser_mode.is_some() {
+            return;
+        }
+
+        self.database.mark_clean();
+    }
+}
@@ -1,18 +1,23 @@
 use std::cell::RefCell;
-use std::collections::{BTreeSet, HashSet};
+use std::collections::{BTreeSet, HashSet, VecDeque};
 use std::ffi::OsString;
 use std::path::Path;
 use std::rc::Rc;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::sync::atomic::AtomicBool;
 
 use ahash::{AHashMap, AHashSet};
 use dirs::home_dir;
+use globset::{Glob, GlobMatcher};
 use gtk::gio::ListStore;
+use gtk::graphene::Rect;
 use gtk::prelude::*;
 use gtk::subclass::prelude::ObjectSubclassIsExt;
 use gtk::{NoSelection, Orientation, SignalListItemFactory};
 use hashlink::LinkedHashMap;
+use path_clean::PathClean;
+use strum_macros::{EnumString, VariantNames};
 
 use super::TabContext;
 use super::element::TabElement;
@@ -20,8 +25,8 @@ use super::flat_dir::CachedDir;
 use super::id::TabId;
 use super::tab::{ClosedTab, Tab};
 use crate::com::{
-    DirSnapshot, DisplayMode, EntryObject, SearchSnapshot, SearchUpdate, SortDir, SortMode,
-    SortSettings, Update,
+    DirSnapshot, DisplayMode, EntryObject, SearchOptions, SearchSnapshot, SearchUpdate, SortDir,
+    SortMode, SortSettings, Update,
 };
 use crate::config::OPTIONS;
 use crate::database::{SavedSplit, Session, SplitChild};
@@ -56,6 +61,111 @@ pub enum TabPosition {
     End,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, VariantNames)]
+#[strum(serialize_all = "lowercase")]
+pub enum PaneDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl PaneDirection {
+    // The orientation of splits along this direction, and whether it points towards their ends.
+    const fn split(self) -> (Orientation, bool) {
+        match self {
+            Self::Left => (Orientation::Horizontal, false),
+            Self::Right => (Orientation::Horizontal, true),
+            Self::Up => (Orientation::Vertical, false),
+            Self::Down => (Orientation::Vertical, true),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResizeAmount {
+    Pixels(i32),
+    Percent(f64),
+}
+
+impl FromStr for ResizeAmount {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(pct) = s.strip_suffix('%') {
+            match pct.parse::<f64>() {
+                Ok(pct) if pct.is_finite() => Ok(Self::Percent(pct)),
+                _ => Err(()),
+            }
+        } else {
+            s.parse().map(Self::Pixels).map_err(|_| ())
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum SelectMode {
+    Replace,
+    Add,
+    Remove,
+}
+
+// Matched against file names, as a glob by default or as a regex with a "re:" prefix.
+#[derive(Debug)]
+pub enum NamePattern {
+    Glob(GlobMatcher),
+    Regex(regex::Regex),
+}
+
+impl FromStr for NamePattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(re) = s.strip_prefix("re:") {
+            regex::Regex::new(re).map(Self::Regex).map_err(|e| format!("Invalid regex {re:?}: {e}"))
+        } else {
+            Glob::new(s)
+                .map(|g| Self::Glob(g.compile_matcher()))
+                .map_err(|e| format!("Invalid glob {s:?}: {e}"))
+        }
+    }
+}
+
+impl NamePattern {
+    fn matches(&self, path: &Path) -> bool {
+        let Some(name) = path.file_name() else {
+            return false;
+        };
+
+        match self {
+            Self::Glob(g) => g.is_match(name),
+            Self::Regex(re) => re.is_match(&name.to_string_lossy()),
+        }
+    }
+}
+
+// How far away `to` is from `from` in the given direction, and how far off-centre it is.
+// None if `to` isn't in that direction or doesn't line up with `from` at all.
+fn directional_distance(from: &Rect, to: &Rect, dir: PaneDirection) -> Option<(f32, f32)> {
+    let xs = |r: &Rect| (r.x(), r.x() + r.width());
+    let ys = |r: &Rect| (r.y(), r.y() + r.height());
+
+    let (gap, (from_start, from_end), (to_start, to_end)) = match dir {
+        PaneDirection::Left => (from.x() - xs(to).1, ys(from), ys(to)),
+        PaneDirection::Right => (to.x() - xs(from).1, ys(from), ys(to)),
+        PaneDirection::Up => (from.y() - ys(to).1, xs(from), xs(to)),
+        PaneDirection::Down => (to.y() - ys(from).1, xs(from), xs(to)),
+    };
+
+    // Allow for rounding, the handles between panes keep them from touching
+    if gap < -1.0 || to_start >= from_end || to_end <= from_start {
+        return None;
+    }
+
+    let offset = ((to_start + to_end) - (from_start + from_end)).abs() / 2.0;
+    Some((gap, offset))
+}
+
 
 // This is tightly coupled the Tab implementation, right now.
 #[derive(Debug)]
@@ -512,6 +622,24 @@ impl TabsList {
         self.switch_active_tab(id)
     }
 
+    // Restores a tab from a session at the end of the list, without activating it.
+    fn restore_tab(&mut self, closed: ClosedTab) {
+        let width = self.pane_container.width() as u32;
+        let (new_tab, element) = Tab::reopen(
+            closed,
+            TabContext {
+                left: &self.tabs,
+                right: &[],
+                cached: &mut self.cached,
+            },
+            width,
+            |w| self.pane_container.append(w),
+        );
+
+        self.tabs.push(new_tab);
+        self.tab_elements.append(&element);
+    }
+
     // For now, tabs always open after the active tab
     // !activate -> background tab
     pub fn open_tab<P: AsRef<Path>>(&mut self, path: P, pos: TabPosition, activate: bool) {
@@ -577,6 +705,9 @@ impl TabsList {
         let orient = if split.horizontal { Orientation::Horizontal } else { Orientation::Vertical };
 
         let paned = self.tabs[first_pos].split(orient, true).unwrap().0;
+        if let Some(position) = split.position {
+            paned.set_position(position);
+        }
         self.tabs[second_pos].force_end_child(paned);
 
         if let SplitChild::Split(split) = &split.start {
@@ -1139,6 +1270,26 @@ impl TabsList {
         self.try_resolve(target, |t| t.search(query.to_owned()));
     }
 
+    pub fn get_search(&self, target: ActionTarget) -> Option<(Arc<Path>, String, SearchOptions)> {
+        let (_id, pos) = self.resolve(target)?;
+        let tab = &self.tabs[pos];
+        Some((tab.dir(), tab.search_query()?, tab.search_options()?))
+    }
+
+    // Opens a new tab after the active tab and immediately starts searching in it.
+    pub fn open_search_tab(&mut self, root: Arc<Path>, query: String, options: SearchOptions) {
+        if OPTIONS.chooser_mode.is_some() {
+            return;
+        }
+
+        let Some(nav_target) = NavTarget::open_or_jump(&root, self) else {
+            return;
+        };
+
+        let id = self.create_tab(TabPosition::After(ActionTarget::Active), nav_target, true);
+        self.find_mut(id).unwrap().search_with(query, options);
+    }
+
     pub fn trash(&mut self, target: ActionTarget) {
         self.try_resolve(target, |t| t.trash());
     }
@@ -1175,14 +1326,60 @@ impl TabsList {
         self.try_resolve(target, |t| t.unselect());
     }
 
+    pub fn select_all(&mut self, target: ActionTarget) {
+        self.try_resolve(target, |t| t.select_all());
+    }
+
+    pub fn invert_selection(&mut self, target: ActionTarget) {
+        self.try_resolve(target, |t| t.invert_selection());
+    }
+
+    pub fn select_pattern(
+        &mut self,
+        target: ActionTarget,
+        pattern: &NamePattern,
+        mode: SelectMode,
+    ) {
+        self.try_resolve(target, |t| t.select_matching(mode, |e| pattern.matches(&e.abs_path)));
+    }
+
+    // Selects everything sharing a mimetype with the current selection.
+    pub fn select_same_type(&mut self, target: ActionTarget) {
+        self.try_resolve(target, |t| {
+            let mimes = t.selected_mimetypes();
+            if mimes.is_empty() {
+                return show_warning("SelectSameType requires at least one selected item");
+            }
+
+            t.select_matching(SelectMode::Replace, |e| mimes.contains(e.mime));
+        });
+    }
+
+    // Relative paths are relative to the tab's directory.
+    pub fn select_paths(&mut self, target: ActionTarget, paths: &str) {
+        self.try_resolve(target, |t| {
+            let dir = t.dir();
+            let paths: AHashSet<_> = paths
+                .lines()
+                .filter(|l| !l.is_empty())
+                .map(|l| dir.join(l).clean())
+                .collect();
+
+            let found = t.select_matching(SelectMode::Replace, |e| paths.contains(&*e.abs_path));
+            if found != paths.len() {
+                show_warning(format!("Selected {found} of {} paths", paths.len()));
+            }
+        });
+    }
+
     pub fn create(&mut self, target: ActionTarget, folder: bool) {
         if self.try_resolve(target, |t| t.create(folder)).is_none() {
             warn!("New{} called with no valid target", if folder { "Folder" } else { "File" });
         };
     }
 
-    pub fn handle_completion(&mut self, completed: CompletionResult) {
-        if let Some(tab) = self.find(completed.tab) {
+    pub fn handle_completion(&mut self, id: TabId, completed: CompletionResult) {
+        if let Some(tab) = self.find(id) {
             tab.handle_completion(completed);
         }
     }
@@ -1200,7 +1397,8 @@ impl TabsList {
         let dg = self.tabs[dest_idx].multi_tab_group();
 
         if sg.as_ref().is_some_and(|sg| dg.as_ref().is_some_and(|dg| Rc::ptr_eq(sg, dg))) {
-            return error!("TODO -- decide how/if to handle moves inside the same group");
+            // Moving a tab onto another tab in the same group swaps their positions.
+            return self.swap_in_group(source, dest);
         }
 
 
@@ -1252,6 +1450,198 @@ impl TabsList {
         }
     }
 
+    fn preserve_group_state(&mut self, group: &Rc<RefCell<Group>>) {
+        let b = group.borrow();
+        for id in std::iter::once(&b.parent).chain(&b.children) {
+            self.tabs.iter_mut().find(|t| t.id() == *id).unwrap().preserve_pane_state();
+        }
+    }
+
+    // Swaps two tabs in the same group, both in the split tree and in the list of tabs.
+    fn swap_in_group(&mut self, a: TabId, b: TabId) {
+        let group = self.find(a).unwrap().multi_tab_group().unwrap();
+        self.preserve_group_state(&group);
+
+        let (ai, bi) = (self.position(a).unwrap(), self.position(b).unwrap());
+        self.tabs[ai].swap_panes(&self.tabs[bi]);
+
+        let ae = self.element_position(a).unwrap();
+        let be = self.element_position(b).unwrap();
+        let (first, second) = (ae.min(be), ae.max(be));
+        let (first_el, second_el) = (self.element(first), self.element(second));
+
+        self.tab_elements.remove(second);
+        self.tab_elements.insert(second, &first_el);
+        self.tab_elements.remove(first);
+        self.tab_elements.insert(first, &second_el);
+
+        // The parent is always the first tab of the group in the list
+        let mut g = group.borrow_mut();
+        if g.parent != a && g.parent != b {
+            return;
+        }
+
+        let (old, new) = if g.parent == a { (a, b) } else { (b, a) };
+        g.parent = new;
+        let pos = g.children.iter().position(|c| *c == new).unwrap();
+        g.children[pos] = old;
+        drop(g);
+
+        self.find(new).unwrap().become_parent();
+        self.find(old).unwrap().become_child();
+    }
+
+    fn resolve_visible_split(&self, target: ActionTarget, cmd: &str) -> Option<(TabId, usize)> {
+        let Some((id, pos)) = self.resolve(target) else {
+            info!("{cmd} called with no matching tab for {target:?}");
+            return None;
+        };
+
+        if !self.tabs[pos].visible() || self.tabs[pos].multi_tab_group().is_none() {
+            info!("{cmd} called on a tab that isn't visible in a split");
+            return None;
+        }
+
+        Some((id, pos))
+    }
+
+    pub fn swap_panes(&mut self, target: ActionTarget) {
+        let Some((id, pos)) = self.resolve_visible_split(target, "SwapPanes") else {
+            return;
+        };
+
+        if let Some(kin) = self.tabs[pos].next_of_kin_by_pane() {
+            self.swap_in_group(id, kin);
+        }
+    }
+
+    pub fn rotate_split(&mut self, target: ActionTarget) {
+        let Some((_id, pos)) = self.resolve_visible_split(target, "RotateSplit") else {
+            return;
+        };
+
+        let group = self.tabs[pos].multi_tab_group().unwrap();
+        self.preserve_group_state(&group);
+        self.tabs[pos].rotate_split();
+    }
+
+    pub fn move_pane(&mut self, target: ActionTarget, orient: Orientation, start: bool) {
+        let Some((id, pos)) = self.resolve_visible_split(target, "MovePane") else {
+            return;
+        };
+
+        let group = self.tabs[pos].multi_tab_group().unwrap();
+        self.preserve_group_state(&group);
+        self.tabs[pos].move_pane(orient, start);
+
+        if self.active == Some(id) {
+            // The view loses focus when it's removed from the tree
+            self.tabs[pos].set_active();
+        }
+    }
+
+    pub fn focus_pane(&mut self, target: ActionTarget, dir: PaneDirection) {
+        let Some((id, pos)) = self.resolve_visible_split(target, "FocusPane") else {
+            return;
+        };
+
+        let Some(from) = self.tabs[pos].pane_bounds() else {
+            return warn!("Couldn't compute bounds for pane {id:?}");
+        };
+
+        let dest = self
+            .tabs
+            .iter()
+            .filter(|t| t.id() != id && t.visible())
+            .filter_map(|t| Some((directional_distance(&from, &t.pane_bounds()?, dir)?, t.id())))
+            .min_by(|((ga, oa), _), ((gb, ob), _)| ga.total_cmp(gb).then(oa.total_cmp(ob)));
+
+        match dest {
+            Some((_, dest)) => self.set_active(dest),
+            None => info!("No pane {dir:?} of {id:?}"),
+        }
+    }
+
+    pub fn cycle_pane(&mut self, target: ActionTarget, forward: bool) {
+        let Some((id, pos)) = self.resolve_visible_split(target, "FocusPane") else {
+            return;
+        };
+
+        let order = self.tabs[pos].pane_tree_order();
+        let Some(i) = order.iter().position(|t| *t == id) else {
+            return error!("Pane {id:?} missing from its own split tree");
+        };
+
+        let dest = if forward {
+            order[(i + 1) % order.len()]
+        } else {
+            order[(i + order.len() - 1) % order.len()]
+        };
+        self.set_active(dest);
+    }
+
+    pub fn resize_pane(&mut self, target: ActionTarget, dir: PaneDirection, amount: ResizeAmount) {
+        let Some((_id, pos)) = self.resolve_visible_split(target, "ResizePane") else {
+            return;
+        };
+
+        let (orient, forward) = dir.split();
+        self.tabs[pos].resize_split(orient, forward, amount);
+    }
+
+    pub fn equalize_panes(&mut self, target: ActionTarget) {
+        let Some((_id, pos)) = self.resolve_visible_split(target, "EqualizePanes") else {
+            return;
+        };
+
+        self.tabs[pos].equalize_splits();
+    }
+
+    // The next pane in tree order after the target, wrapping around.
+    fn other_pane(&self, target: ActionTarget, cmd: &str) -> Option<(usize, usize)> {
+        let (id, pos) = self.resolve_visible_split(target, cmd)?;
+
+        let order = self.tabs[pos].pane_tree_order();
+        let i = order.iter().position(|t| *t == id)?;
+        let other = order[(i + 1) % order.len()];
+
+        Some((pos, self.position(other)?))
+    }
+
+    pub fn operate_on_other_pane(
+        &self,
+        target: ActionTarget,
+        cmd: &str,
+        kind: fn(Arc<Path>) -> operations::Kind,
+    ) {
+        let Some((src, dest)) = self.other_pane(target, cmd) else {
+            return;
+        };
+
+        let (src, dest) = (&self.tabs[src], &self.tabs[dest]);
+        if !dest.accepts_paste() {
+            return show_warning("Cannot paste into the other pane");
+        }
+
+        let files = src.selected_paths();
+        if files.is_empty() {
+            return info!("{cmd} called with no selected files");
+        }
+
+        // Use the destination tab so it scrolls to the new files
+        let kind = kind(dest.dir());
+        gui_run(|g| g.start_operation(dest.id(), kind, files));
+    }
+
+    pub fn sync_other_pane(&mut self, target: ActionTarget) {
+        let Some((src, dest)) = self.other_pane(target, "SyncOtherPane") else {
+            return;
+        };
+
+        let (path, dest) = (self.tabs[src].dir(), self.tabs[dest].id());
+        self.navigate_open_tab(dest, &path);
+    }
+
     pub fn get_session(&self) -> Option<Session> {
         if OPTIONS.chooser_mode.is_some() {
             return None;
@@ -1265,6 +1655,7 @@ impl TabsList {
         let tabs: AHashMap<_, _> = self.tabs.iter().map(|t| (t.id(), t)).collect();
 
         let mut numbered_ids = AHashMap::new();
+        let mut saved_tabs = Vec::with_capacity(self.tabs.len());
 
         let paths = self
             .tab_elements
@@ -1274,6 +1665,7 @@ impl TabsList {
             .map(|(n, el)| {
                 let id = *el.imp().tab.get().unwrap();
                 numbered_ids.insert(id, n as u32);
+                saved_tabs.push(tabs[&id].save_session());
                 tabs[&id].dir()
             })
             .collect();
@@ -1286,7 +1678,7 @@ impl TabsList {
             }
         }
 
-        Some(Session { paths, groups })
+        Some(Session { paths, groups, tabs: saved_tabs })
     }
 
     pub fn load_session(&mut self, session: Session) {
@@ -1296,9 +1688,15 @@ impl TabsList {
 
         // Take advantage of existing data if we can.
         let old_tabs = self.tabs.len();
-        for path in session.paths {
-            let nav_target = NavTarget::assume_dir(path);
-            self.create_tab(TabPosition::End, nav_target, false);
+        if session.tabs.is_empty() {
+            for path in session.paths {
+                let nav_target = NavTarget::assume_dir(path);
+                self.create_tab(TabPosition::End, nav_target, false);
+            }
+        } else {
+            for saved in session.tabs {
+                self.restore_tab(ClosedTab::from_session(next_id(), saved));
+            }
         }
 
         for n in 0..old_tabs {
@@ -1324,6 +1722,111 @@ impl TabsList {
         }
     }
 
+    pub fn ipc_tabs(&self) -> serde_json::Value {
+        self.tab_elements
+            .iter::<TabElement>()
+            .map(Result::unwrap)
+            .map(|el| {
+                let tab = self.find(*el.imp().tab.get().unwrap()).unwrap();
+                let mut json = tab.ipc_json();
+                json["active"] = (self.active == Some(tab.id())).into();
+                json["group"] =
+                    tab.multi_tab_group().map(|g| g.borrow().parent.raw()).into();
+                json
+            })
+            .collect()
+    }
+
+    pub fn tab_ids(&self) -> AHashSet<TabId> {
+        self.tabs.iter().map(Tab::id).collect()
+    }
+
+    // A tab opened since `before` was taken, preferring the active tab.
+    pub fn opened_tab(&self, before: &AHashSet<TabId>) -> Option<TabId> {
+        self.active
+            .filter(|id| !before.contains(id))
+            .or_else(|| self.tabs.iter().map(Tab::id).find(|id| !before.contains(id)))
+    }
+
+    pub fn ipc_result_tab(
+        &self,
+        target: ActionTarget,
+        before: &AHashSet<TabId>,
+    ) -> Option<serde_json::Value> {
+        let tab = match self.opened_tab(before) {
+            Some(id) => self.find(id),
+            None => self.resolve(target).map(|(_, pos)| &self.tabs[pos]),
+        };
+
+        tab.map(Tab::ipc_json)
+    }
+
+    pub fn ipc_active(&self) -> serde_json::Value {
+        self.active.and_then(|id| self.find(id)).map(Tab::ipc_json).into()
+    }
+
+    pub fn target_selection(&self, target: ActionTarget) -> VecDeque<Arc<Path>> {
+        self.resolve(target).map(|(_, pos)| self.tabs[pos].selected_paths()).unwrap_or_default()
+    }
+
+    pub fn ipc_selection(&self, target: ActionTarget) -> Vec<String> {
+        self.target_selection(target)
+            .iter()
+            .map(|p| p.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    pub fn active_selection(&self) -> Vec<Arc<Path>> {
+        self.active
+            .and_then(|id| self.find(id))
+            .map(|t| t.selected_paths().into())
+            .unwrap_or_default()
+    }
+
+    // The active tab with its selection and directory, as used for filtering custom actions.
+    pub fn active_context(&self) -> Option<(TabId, Vec<EntryObject>, Arc<Path>)> {
+        let tab = self.active.and_then(|id| self.find(id))?;
+        Some((tab.id(), tab.selected_entries(), tab.dir()))
+    }
+
+    pub fn target_dir(&self, target: ActionTarget) -> Option<Arc<Path>> {
+        self.resolve(target).map(|(_, pos)| self.tabs[pos].dir())
+    }
+
+    // The layout is the group's split tree as JSON, with tabs as indices into AWFM_GROUP_PANES.
+    fn group_env_vars(&self, tab: &Tab, env: &mut Vec<(String, OsString)>) {
+        if !tab.visible() || tab.multi_tab_group().is_none() {
+            return;
+        }
+
+        let order = tab.pane_tree_order();
+        let Some(index) = order.iter().position(|id| *id == tab.id()) else {
+            return error!("Pane {:?} missing from its own split tree", tab.id());
+        };
+
+        if index > 0 {
+            self.find(order[index - 1]).unwrap().env_vars("AWFM_PREV_PANE", env);
+        }
+        if let Some(next) = order.get(index + 1) {
+            self.find(*next).unwrap().env_vars("AWFM_NEXT_PANE", env);
+        }
+
+        let mut panes = OsString::new();
+        for (i, id) in order.iter().enumerate() {
+            if i > 0 {
+                panes.push("\n");
+            }
+            panes.push(self.find(*id).unwrap().dir().as_os_str());
+        }
+        env.push(("AWFM_GROUP_PANES".to_owned(), panes));
+
+        let ids: AHashMap<_, _> = order.into_iter().zip(0..).collect();
+        match serde_json::to_string(&tab.save_group(&ids).split) {
+            Ok(layout) => env.push(("AWFM_GROUP_LAYOUT".to_owned(), layout.into())),
+            Err(e) => error!("Failed to serialize group layout: {e}"),
+        }
+    }
+
     pub fn get_env(&self) -> Vec<(String, OsString)> {
         let mut env: Vec<(String, OsString)> = Vec::new();
 
@@ -1331,6 +1834,8 @@ impl TabsList {
             let tab = self.find(active).unwrap();
             tab.env_vars("AWFM_CURRENT_TAB", &mut env);
             env.push(("AWFM_SELECTION".to_owned(), tab.selection_env_str()));
+            tab.focus_and_settings_env_vars(&mut env);
+            self.group_env_vars(tab, &mut env);
 
             if let Some(index) = self.element_position(active) {
                 if index > 0 {
@@ -1384,3 +1889,59 @@ impl TabsList {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::str::FromStr;
+
+    use super::{NamePattern, ResizeAmount};
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        NamePattern::from_str(pattern).unwrap().matches(Path::new(path))
+    }
+
+    #[test]
+    fn resize_amounts() {
+        assert_eq!(ResizeAmount::from_str("50"), Ok(ResizeAmount::Pixels(50)));
+        assert_eq!(ResizeAmount::from_str("-20"), Ok(ResizeAmount::Pixels(-20)));
+        assert_eq!(ResizeAmount::from_str("10%"), Ok(ResizeAmount::Percent(10.0)));
+        assert_eq!(ResizeAmount::from_str("-2.5%"), Ok(ResizeAmount::Percent(-2.5)));
+
+        assert_eq!(ResizeAmount::from_str(""), Err(()));
+        assert_eq!(ResizeAmount::from_str("%"), Err(()));
+        assert_eq!(ResizeAmount::from_str("1.5"), Err(()));
+        assert_eq!(ResizeAmount::from_str("10px"), Err(()));
+        assert_eq!(ResizeAmount::from_str("inf%"), Err(()));
+        assert_eq!(ResizeAmount::from_str("NaN%"), Err(()));
+    }
+
+    #[test]
+    fn globs() {
+        assert!(matches("*.txt", "/home/user/notes.txt"));
+        assert!(matches("notes.*", "notes.txt"));
+        assert!(matches("file?.rs", "/src/file1.rs"));
+        assert!(matches("*.{jpg,png}", "/pics/a.png"));
+        // Only the file name is matched
+        assert!(!matches("user*", "/home/user/notes.txt"));
+        assert!(!matches("*.txt", "/home/user/notes.txt.bak"));
+        assert!(!matches("*.TXT", "/home/user/notes.txt"));
+        assert!(!matches("*", "/"));
+    }
+
+    #[test]
+    fn regexes() {
+        assert!(matches(r"re:^\d+\.log$", "/var/log/123.log"));
+        assert!(matches("re:draft", "/docs/old-draft-2.md"));
+        assert!(!matches(r"re:^\d+\.log$", "/var/log/a123.log"));
+        assert!(!matches("re:^docs$", "/docs/readme"));
+    }
+
+    #[test]
+    fn invalid_patterns() {
+        assert!(NamePattern::from_str("[").is_err());
+        assert!(NamePattern::from_str("re:(").is_err());
+        // Without the prefix it's a glob, even if it looks like a regex
+        assert!(NamePattern::from_str("(").is_ok());
+    }
+}
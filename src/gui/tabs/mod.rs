@@ -11,6 +11,7 @@ use self::contents::Contents;
 use self::list::TabsList;
 use crate::com::{DisplayMode, Entry, EntryObject};
 use crate::config::OPTIONS;
+use crate::database::{SavedFocus, SavedHistory, SavedPath, SavedScroll};
 use crate::gui::show_warning;
 
 mod contents;
@@ -72,10 +73,49 @@ impl PartiallyAppliedUpdate {
 #[derive(Debug, Clone)]
 struct HistoryEntry {
     location: Arc<Path>,
+    // Returning to a search reruns it with the configured options
     search: Option<String>,
     state: PaneState,
 }
 
+impl HistoryEntry {
+    // Precise scroll positions depend on the window size so they aren't worth saving.
+    fn save(&self) -> SavedHistory {
+        SavedHistory {
+            location: SavedPath(self.location.clone()),
+            search: self.search.clone(),
+            scroll: self
+                .state
+                .scroll
+                .as_ref()
+                .map(|sp| SavedScroll { path: SavedPath(sp.path.clone()), index: sp.index }),
+            focus: self
+                .state
+                .focus
+                .as_ref()
+                .map(|fs| SavedFocus { path: SavedPath(fs.path.clone()), select: fs.select }),
+        }
+    }
+
+    fn restore(saved: SavedHistory) -> Self {
+        let state = PaneState {
+            scroll: saved.scroll.map(|sp| ScrollPosition {
+                precise: None,
+                path: sp.path.0,
+                index: sp.index,
+            }),
+            focus: saved.focus.map(|fs| FocusState { path: fs.path.0, select: fs.select }),
+            selection: Vec::new(),
+        };
+
+        Self {
+            location: saved.location.0,
+            search: saved.search,
+            state,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct PrecisePosition {
     position: f64,
@@ -108,6 +148,8 @@ struct FocusState {
 struct PaneState {
     pub scroll: Option<ScrollPosition>,
     pub focus: Option<FocusState>,
+    // Only set when restoring sessions, history does not track selections.
+    pub selection: Vec<Arc<Path>>,
 }
 
 impl PaneState {
@@ -115,6 +157,7 @@ impl PaneState {
         Self {
             scroll: jump.clone().map(|path| ScrollPosition { precise: None, path, index: 0 }),
             focus: jump.map(|path| FocusState { path, select: true }),
+            selection: Vec::new(),
         }
     }
 }
@@ -290,4 +333,15 @@ pub mod id {
             TabId(self.0)
         }
     }
+
+    // Only for referring to tabs from outside the process, like over IPC.
+    impl TabId {
+        pub const fn raw(self) -> u64 {
+            self.0
+        }
+
+        pub const fn from_raw(n: u64) -> Self {
+            Self(n)
+        }
+    }
 }
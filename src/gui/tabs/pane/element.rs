@@ -370,6 +370,11 @@ impl PaneElement {
             }
         }
 
+        // Sequences and modes take precedence over seeking, but not over the seek's own keys
+        if gui_run(|g| g.keys_captured(key, mods)) {
+            return Propagation::Proceed;
+        }
+
         let Some(c) = key.to_unicode() else {
             return Propagation::Proceed;
         };
@@ -10,7 +10,7 @@ use ahash::AHashMap;
 use gtk::gdk::{DragAction, Key, ModifierType, Rectangle};
 use gtk::gio::Icon;
 use gtk::glib::{self, Propagation, WeakRef};
-use gtk::graphene::Point;
+use gtk::graphene::{Point, Rect};
 use gtk::prelude::*;
 use gtk::subclass::prelude::ObjectSubclassIsExt;
 use gtk::{
@@ -28,11 +28,11 @@ use crate::com::{DirSettings, DisplayMode, EntryObject, SignalHolder};
 use crate::config::{CONFIG, OPTIONS};
 use crate::database::{SavedSplit, SplitChild};
 use crate::gui::clipboard::{ClipboardOp, URIS};
-use crate::gui::tabs::list::TabPosition;
+use crate::gui::tabs::list::{ResizeAmount, TabPosition};
 use crate::gui::tabs::{FocusState, NavTarget};
 use crate::gui::{
-    ActionTarget, CompletionResult, ControllerDisconnector, DebugIgnore, ManagerAction, gui_run,
-    tabs_run,
+    ActionTarget, CompletionResult, CompletionTarget, ControllerDisconnector, DebugIgnore,
+    ManagerAction, gui_run, tabs_run,
 };
 use crate::natsort::normalize_lowercase;
 
@@ -340,7 +340,11 @@ impl Pane {
                 path = cwd.to_path_buf().join(path);
             }
 
-            gui_run(|g| g.send_manager(ManagerAction::Complete(path, initial, tab)));
+            gui_run(|g| {
+                let recent = g.recent_dirs.borrow().clone();
+                let target = CompletionTarget::Tab(tab);
+                g.send_manager(ManagerAction::Complete(path, initial, target, recent))
+            });
             Propagation::Stop
         });
         // Needed to catch ctrl+z
@@ -720,7 +724,11 @@ impl Pane {
             None
         };
 
-        PaneState { scroll, focus }
+        PaneState { scroll, focus, selection: Vec::new() }
+    }
+
+    pub fn focused_path(&self) -> Option<Arc<Path>> {
+        self.view.focus_child().map(|(_child, eo)| eo.get().abs_path.clone())
     }
 
     pub fn start_apply_state(&mut self, state: PaneState) {
@@ -798,6 +806,14 @@ impl Pane {
 
         self.view.scroll_to(pos, scroll_flags);
 
+        for path in &state.selection {
+            if let Some(eo) = EntryObject::lookup(path)
+                && let Some(pos) = list.filtered_position_by_sorted(&eo.get())
+            {
+                list.selection.select_item(pos, false);
+            }
+        }
+
         if let Some(precise) = &precise {
             let vadjust = self.element.imp().scroller.vadjustment();
 
@@ -906,6 +922,158 @@ impl Pane {
         Some(kin)
     }
 
+    // Swaps the positions of two panes in the same split tree.
+    pub fn swap_with(&self, other: &Self) {
+        let (Some(pa), Some(pb)) = (
+            self.element.parent().and_downcast::<gtk::Paned>(),
+            other.element.parent().and_downcast::<gtk::Paned>(),
+        ) else {
+            return error!(
+                "Tried to swap panes {:?} and {:?} outside of a split",
+                self.tab, other.tab
+            );
+        };
+
+        info!("Swapping panes {:?} and {:?}", self.tab, other.tab);
+
+        let a_start = is_start_child(&pa, &self.element);
+        let b_start = is_start_child(&pb, &other.element);
+        let (pos_a, pos_b) = (pa.position(), pb.position());
+
+        let detach = |paned: &gtk::Paned, start: bool| {
+            if start {
+                paned.set_start_child(Widget::NONE);
+            } else {
+                paned.set_end_child(Widget::NONE);
+            }
+        };
+        detach(&pa, a_start);
+        detach(&pb, b_start);
+
+        let attach = |paned: &gtk::Paned, start: bool, w: &PaneElement| {
+            if start {
+                paned.set_start_child(Some(w));
+            } else {
+                paned.set_end_child(Some(w));
+            }
+        };
+        attach(&pa, a_start, &other.element);
+        attach(&pb, b_start, &self.element);
+
+        pa.set_position(pos_a);
+        pb.set_position(pos_b);
+    }
+
+    // Flips the split containing this pane between horizontal and vertical.
+    pub fn rotate_split(&self) {
+        let Some(paned) = self.element.parent().and_downcast::<gtk::Paned>() else {
+            return warn!("Tried to rotate pane {:?} outside of a split", self.tab);
+        };
+
+        let (w, h) = (paned.width().max(1), paned.height().max(1));
+        let pos = paned.position();
+
+        let (orient, pos) = if paned.orientation() == Orientation::Horizontal {
+            (Orientation::Vertical, pos * h / w)
+        } else {
+            (Orientation::Horizontal, pos * w / h)
+        };
+
+        info!("Rotating split containing {:?} to {orient:?}", self.tab);
+        paned.set_orientation(orient);
+        paned.set_position(pos);
+    }
+
+    // Moves this pane to one edge of its group, spanning the entire group.
+    pub fn move_to_edge(&self, orient: Orientation, start: bool) {
+        let Some(parent) = self.element.parent().and_downcast::<gtk::Paned>() else {
+            return warn!("Tried to move pane {:?} outside of a split", self.tab);
+        };
+
+        let sibling = if is_start_child(&parent, &self.element) {
+            parent.end_child().unwrap()
+        } else {
+            parent.start_child().unwrap()
+        };
+
+        self.remove_from_parent();
+
+        let root = split_root(sibling);
+        let container = root.parent().and_downcast::<gtk::Box>().unwrap();
+        let (w, h) = (root.width(), root.height());
+        container.remove(&root);
+
+        info!("Moving pane {:?} to the {} edge", self.tab, if start { "start" } else { "end" });
+
+        let paned = gtk::Paned::builder()
+            .orientation(orient)
+            .shrink_start_child(false)
+            .shrink_end_child(false)
+            .build();
+
+        if start {
+            paned.set_start_child(Some(&self.element));
+            paned.set_end_child(Some(&root));
+        } else {
+            paned.set_start_child(Some(&root));
+            paned.set_end_child(Some(&self.element));
+        }
+
+        paned.set_position(if orient == Orientation::Horizontal { w / 2 } else { h / 2 });
+        container.append(&paned);
+    }
+
+    // The area occupied by this pane, relative to the window.
+    pub fn bounds(&self) -> Option<Rect> {
+        self.element.compute_bounds(&self.element.root()?)
+    }
+
+    // All panes in this pane's split tree, ordered from the start to the end.
+    pub fn tree_order(&self) -> Vec<TabId> {
+        let mut out = Vec::new();
+        collect_descendents(split_root(self.element.clone().upcast()), &mut out);
+        out
+    }
+
+    // Moves the nearest divider on the given side of this pane towards that side.
+    pub fn resize_split(&self, orient: Orientation, forward: bool, amount: ResizeAmount) {
+        let mut child: gtk::Widget = self.element.clone().upcast();
+        while let Some(paned) = child.parent().and_downcast::<gtk::Paned>() {
+            // Moving right or down needs a split with this pane before its divider, and vice versa.
+            let side = if forward { paned.start_child() } else { paned.end_child() };
+            if paned.orientation() != orient || side.as_ref() != Some(&child) {
+                child = paned.upcast();
+                continue;
+            }
+
+            let size = match orient {
+                Orientation::Horizontal => paned.width(),
+                _ => paned.height(),
+            };
+            let delta = match amount {
+                ResizeAmount::Pixels(px) => px,
+                ResizeAmount::Percent(pct) => (f64::from(size) * pct / 100.0).round() as i32,
+            };
+            let delta = if forward { delta } else { -delta };
+
+            let pos = (paned.position() + delta).clamp(paned.min_position(), paned.max_position());
+            debug!("Resizing split containing {:?} to {pos}", self.tab);
+            return paned.set_position(pos);
+        }
+
+        info!("No {orient:?} split to resize for pane {:?}", self.tab);
+    }
+
+    // Resizes every split in this pane's tree so that all panes are of equal size.
+    pub fn equalize_splits(&self) {
+        let Ok(root) = split_root(self.element.clone().upcast()).downcast::<gtk::Paned>() else {
+            return;
+        };
+
+        info!("Equalizing splits containing {:?}", self.tab);
+        equalize(&root, root.width(), root.height());
+    }
+
     pub fn workaround_disable_rubberband(&self) {
         match &self.view {
             View::Icons(v) => v.workaround_disable_rubberband(),
@@ -1006,7 +1174,74 @@ fn save_tree(paned: gtk::Paned, ids: &AHashMap<TabId, u32>) -> SavedSplit {
 
     let horizontal = paned.orientation() == Orientation::Horizontal;
 
-    SavedSplit { horizontal, start, end }
+    SavedSplit {
+        horizontal,
+        start,
+        end,
+        position: Some(paned.position()),
+    }
+}
+
+fn is_start_child(paned: &gtk::Paned, widget: &impl IsA<Widget>) -> bool {
+    paned.start_child().is_some_and(|c| &c == widget.upcast_ref::<Widget>())
+}
+
+fn split_root(mut widget: Widget) -> Widget {
+    while let Some(paned) = widget.parent().and_downcast::<gtk::Paned>() {
+        widget = paned.upcast();
+    }
+
+    widget
+}
+
+fn collect_descendents(widget: Widget, out: &mut Vec<TabId>) {
+    match widget.downcast::<gtk::Paned>() {
+        Ok(paned) => {
+            collect_descendents(paned.start_child().unwrap(), out);
+            collect_descendents(paned.end_child().unwrap(), out);
+        }
+        Err(widget) => {
+            out.push(*widget.downcast::<PaneElement>().unwrap().imp().tab.get().unwrap());
+        }
+    }
+}
+
+// The number of panes laid out side by side along orient.
+fn equal_weight(widget: &Widget, orient: Orientation) -> i32 {
+    match widget.downcast_ref::<gtk::Paned>() {
+        Some(paned) if paned.orientation() == orient => {
+            equal_weight(&paned.start_child().unwrap(), orient)
+                + equal_weight(&paned.end_child().unwrap(), orient)
+        }
+        _ => 1,
+    }
+}
+
+// Children won't be reallocated until the next layout, so pass the sizes down explicitly.
+fn equalize(paned: &gtk::Paned, width: i32, height: i32) {
+    let orient = paned.orientation();
+    let start = paned.start_child().unwrap();
+    let end = paned.end_child().unwrap();
+
+    let start_weight = equal_weight(&start, orient);
+    let total = start_weight + equal_weight(&end, orient);
+
+    let (start_size, end_size) = if orient == Orientation::Horizontal {
+        let pos = width * start_weight / total;
+        paned.set_position(pos);
+        ((pos, height), (width - pos, height))
+    } else {
+        let pos = height * start_weight / total;
+        paned.set_position(pos);
+        ((width, pos), (width, height - pos))
+    };
+
+    if let Some(p) = start.downcast_ref::<gtk::Paned>() {
+        equalize(p, start_size.0, start_size.1);
+    }
+    if let Some(p) = end.downcast_ref::<gtk::Paned>() {
+        equalize(p, end_size.0, end_size.1);
+    }
 }
 
 fn firstmost_descendent(mut widget: Widget) -> TabId {
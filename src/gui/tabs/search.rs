@@ -10,8 +10,8 @@ use gtk::CustomFilter;
 use super::contents::Contents;
 use super::PartiallyAppliedUpdate;
 use crate::com::{
-    DirSettings, EntryObject, EntryObjectSnapshot, GetEntry, ManagerAction, SearchSnapshot,
-    SearchUpdate, SortSettings, Update,
+    DirSettings, EntryObject, EntryObjectSnapshot, GetEntry, ManagerAction, SearchOptions,
+    SearchSnapshot, SearchUpdate, SortSettings, Update,
 };
 use crate::gui::gui_run;
 use crate::natsort::normalize_lowercase;
@@ -30,6 +30,7 @@ enum State {
 #[derive(Debug)]
 pub(super) struct Search {
     path: Arc<Path>,
+    options: SearchOptions,
     state: State,
     // This contains everything in tab.contents plus items from subdirectories.
     contents: Contents,
@@ -55,7 +56,8 @@ impl Search {
     pub fn start_load(&mut self) -> bool {
         match self.state {
             State::Unloaded => {
-                self.state = State::Loading(SearchId::new(self.path.clone()), Vec::new());
+                let id = SearchId::new(self.path.clone(), self.options);
+                self.state = State::Loading(id, Vec::new());
                 true
             }
             State::Loading(..) | State::Done(_) => false,
@@ -72,7 +74,16 @@ impl Search {
         (self.original.clone(), self.normalized.clone())
     }
 
-    pub fn new(path: Arc<Path>, flat_contents: &Contents, query: String) -> Self {
+    pub const fn options(&self) -> SearchOptions {
+        self.options
+    }
+
+    pub fn new(
+        path: Arc<Path>,
+        options: SearchOptions,
+        flat_contents: &Contents,
+        query: String,
+    ) -> Self {
         let state = State::Unloaded;
         let (contents, filter) = Contents::search_from(flat_contents);
 
@@ -89,6 +100,7 @@ impl Search {
 
         Self {
             path,
+            options,
             state,
             contents,
             original,
@@ -106,6 +118,7 @@ impl Search {
 
         Self {
             path: self.path.clone(),
+            options: self.options,
             state,
             contents,
             original,
@@ -260,22 +273,23 @@ impl Search {
 
 
 // The pointer is used for uniqueness, the boolean is used to signal cancellation on drop.
+// The options tell the manager whether there should have been a watcher to remove.
 #[derive(Debug)]
-struct SearchId(Arc<AtomicBool>);
+struct SearchId(Arc<AtomicBool>, SearchOptions);
 
 impl Drop for SearchId {
     fn drop(&mut self) {
         self.0.store(true, Ordering::Relaxed);
-        gui_run(|g| g.send_manager(ManagerAction::EndSearch(self.0.clone())))
+        gui_run(|g| g.send_manager(ManagerAction::EndSearch(self.0.clone(), self.1)))
     }
 }
 
 impl SearchId {
-    fn new(path: Arc<Path>) -> Self {
+    fn new(path: Arc<Path>, options: SearchOptions) -> Self {
         let id: Arc<AtomicBool> = Arc::default();
 
-        gui_run(|g| g.send_manager(ManagerAction::Search(path, id.clone())));
+        gui_run(|g| g.send_manager(ManagerAction::Search(path, options, id.clone())));
 
-        Self(id)
+        Self(id, options)
     }
 }
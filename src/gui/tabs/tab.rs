@@ -12,6 +12,7 @@ use std::time::Instant;
 use TabPane as TP;
 use ahash::{AHashMap, AHashSet};
 use gtk::gio::Cancellable;
+use gtk::graphene::Rect;
 use gtk::prelude::*;
 use gtk::subclass::prelude::ObjectSubclassIsExt;
 use gtk::{AlertDialog, Bitset, Orientation, PopoverMenu, SelectionModel, Widget, glib};
@@ -22,16 +23,17 @@ use super::contents::Contents;
 use super::element::TabElement;
 use super::flat_dir::FlatDir;
 use super::id::{TabId, TabUid};
-use super::list::Group;
+use super::list::{Group, ResizeAmount, SelectMode};
 use super::pane::Pane;
 use super::search::Search;
 use super::{CachedDir, HistoryEntry, NavTarget, PaneState, TabContext};
 use crate::com::{
-    DirSettings, DirSnapshot, DisplayMode, EntryObject, EntryObjectSnapshot, ExistingEntry,
-    GetEntry, ManagerAction, SearchSnapshot, SearchUpdate, SortDir, SortMode, SortSettings,
+    DirSettings, DirSnapshot, DisplayMode, Entry, EntryObject, EntryObjectSnapshot,
+    ExistingEntry, GetEntry, ManagerAction, SearchOptions, SearchSnapshot, SearchUpdate, SortDir,
+    SortMode, SortSettings,
 };
 use crate::config::CONFIG;
-use crate::database::SavedGroup;
+use crate::database::{SavedGroup, SavedPath, SavedTab};
 use crate::gui::chooser::chooser_run;
 use crate::gui::clipboard::{ClipboardOp, SelectionProvider, handle_clipboard, handle_drop};
 use crate::gui::operations::{self, Kind, Outcome};
@@ -229,6 +231,22 @@ pub(super) struct ClosedTab {
     future: Vec<HistoryEntry>,
 }
 
+impl ClosedTab {
+    // Restored tabs from sessions are treated like closed tabs being reopened.
+    pub fn from_session(id: TabUid, saved: SavedTab) -> Self {
+        let mut current = HistoryEntry::restore(saved.current);
+        current.state.selection = saved.selection.into_iter().map(|p| p.0).collect();
+
+        Self {
+            id,
+            after: None,
+            current,
+            past: saved.past.into_iter().map(HistoryEntry::restore).collect(),
+            future: saved.future.into_iter().map(HistoryEntry::restore).collect(),
+        }
+    }
+}
+
 impl Tab {
     pub const fn id(&self) -> TabId {
         self.id.copy()
@@ -282,6 +300,14 @@ impl Tab {
         self.dir.path().clone()
     }
 
+    pub fn search_query(&self) -> Option<String> {
+        self.search.as_ref().map(|s| s.query().0.borrow().clone())
+    }
+
+    pub fn search_options(&self) -> Option<SearchOptions> {
+        self.search.as_ref().map(Search::options)
+    }
+
     pub fn overlaps(&self, p: &Arc<Path>) -> bool {
         // TODO [refresh] actually consider overlapping searches
         self.matches_arc(p)
@@ -320,7 +346,10 @@ impl Tab {
     ) -> (Self, TabElement) {
         debug!("Opening tab {id:?} to {target:?}");
         // fetch metatada synchronously, even with a donor
-        let settings = gui_run(|g| g.database.get(target.dir.clone()));
+        let settings = gui_run(|g| {
+            g.visit_dir(target.dir.clone());
+            g.database.get(target.dir.clone())
+        });
         chooser_run(|c| c.root(&target.dir));
 
         let element = TabElement::new(id.copy(), &target.dir);
@@ -469,7 +498,7 @@ impl Tab {
 
         // Open search, if applicable, after copying flat state.
         if let Some(query) = closed.current.search {
-            t.open_search(query);
+            t.open_search(query, SearchOptions::configured());
         }
 
         (t, element)
@@ -513,9 +542,9 @@ impl Tab {
         Some((paned, self.get_or_start_group()))
     }
 
-    fn open_search(&mut self, query: String) {
+    fn open_search(&mut self, query: String, options: SearchOptions) {
         trace!("Creating Search for {:?}", self.id);
-        let mut search = Search::new(self.dir.path().clone(), &self.contents, query);
+        let mut search = Search::new(self.dir.path().clone(), options, &self.contents, query);
         chooser_run(|c| c.root(self.dir.path()));
 
         self.element.search_title(self.dir.path());
@@ -550,6 +579,11 @@ impl Tab {
 
     // Starts a new search or updates an existing one with a new query.
     pub fn search(&mut self, query: String) {
+        self.search_with(query, SearchOptions::configured());
+    }
+
+    // The options only apply when this starts a new search.
+    pub fn search_with(&mut self, query: String, options: SearchOptions) {
         if let Some(_search) = &mut self.search {
             self.pane.update_search(&query);
             return;
@@ -557,7 +591,7 @@ impl Tab {
 
         self.past.push(self.current_history());
         self.future.clear();
-        self.open_search(query);
+        self.open_search(query, options);
     }
 
     // Only make this take &mut [Self] if truly necessary
@@ -823,7 +857,10 @@ impl Tab {
             .map(|(path, watch)| self.contents.list_into_cache(path, watch));
 
         let old_settings = self.settings;
-        self.settings = gui_run(|g| g.database.get(self.dir.path().clone()));
+        self.settings = gui_run(|g| {
+            g.visit_dir(self.dir.path().clone());
+            g.database.get(self.dir.path().clone())
+        });
 
         if was_search {
             // Clear so we don't flicker
@@ -1072,7 +1109,7 @@ impl Tab {
         // Shouldn't be a jump, could be a search starting/ending.
         if hist.location == *self.dir.path() {
             if let Some(query) = hist.search {
-                self.open_search(query);
+                self.open_search(query, SearchOptions::configured());
             } else {
                 self.close_search();
             }
@@ -1091,7 +1128,7 @@ impl Tab {
         }
 
         if let Some(query) = hist.search {
-            self.open_search(query);
+            self.open_search(query, SearchOptions::configured());
         } else {
             self.close_search();
         }
@@ -1336,6 +1373,50 @@ impl Tab {
         self.element.set_child(false);
     }
 
+    pub fn become_child(&self) {
+        self.element.set_child(true);
+    }
+
+    // Moving panes around the split tree can reset their scroll positions, so save the current
+    // state and reapply it once the pane has been reallocated.
+    pub fn preserve_pane_state(&mut self) {
+        if !matches!(self.pane, TabPane::Displayed(_)) {
+            return;
+        }
+
+        let state = self.current_history().state;
+        self.pane.overwrite_state(state);
+        self.maybe_start_apply_state();
+    }
+
+    pub fn swap_panes(&self, other: &Self) {
+        self.pane.swap_with(&other.pane);
+    }
+
+    pub fn rotate_split(&self) {
+        self.pane.rotate_split();
+    }
+
+    pub fn move_pane(&self, orient: Orientation, start: bool) {
+        self.pane.move_to_edge(orient, start);
+    }
+
+    pub fn pane_bounds(&self) -> Option<Rect> {
+        self.pane.bounds()
+    }
+
+    pub fn pane_tree_order(&self) -> Vec<TabId> {
+        self.pane.tree_order()
+    }
+
+    pub fn resize_split(&self, orient: Orientation, forward: bool, amount: ResizeAmount) {
+        self.pane.resize_split(orient, forward, amount);
+    }
+
+    pub fn equalize_splits(&self) {
+        self.pane.equalize_splits();
+    }
+
     pub fn next_of_kin_by_pane(&self) -> Option<TabId> {
         // If it's not in a group, it will not have kin
         self.multi_tab_group().and_then(|_| self.pane.next_of_kin())
@@ -1523,6 +1604,10 @@ impl Tab {
         handle_drop(drop_ev, self.id(), self.dir())
     }
 
+    pub fn selected_paths(&self) -> VecDeque<Arc<Path>> {
+        Selected::from(self.visible_selection()).map(|eo| eo.get().abs_path.clone()).collect()
+    }
+
     fn run_deletion(tab: TabId, files: VecDeque<Arc<Path>>, kind: Kind) {
         gui_run(|g| g.start_operation(tab, kind, files))
     }
@@ -1602,6 +1687,57 @@ impl Tab {
         self.clear_selection();
     }
 
+    pub fn select_all(&self) {
+        if self.unloaded() {
+            return;
+        }
+
+        self.visible_selection().select_all();
+    }
+
+    pub fn invert_selection(&self) {
+        if self.unloaded() {
+            return;
+        }
+
+        let selection = self.visible_selection();
+        let all = Bitset::new_range(0, selection.n_items());
+
+        let inverted = all.copy();
+        inverted.difference(&selection.selection());
+        selection.set_selection(&inverted, &all);
+    }
+
+    // Returns the number of matching items, which are selected or unselected based on mode.
+    pub fn select_matching(&self, mode: SelectMode, matches: impl Fn(&Entry) -> bool) -> usize {
+        if self.unloaded() {
+            return 0;
+        }
+
+        let selection = self.visible_selection();
+        let n = selection.n_items();
+
+        let matched = Bitset::new_empty();
+        for i in 0..n {
+            let eo = selection.item(i).and_downcast::<EntryObject>().unwrap();
+            if matches(&eo.get()) {
+                matched.add(i);
+            }
+        }
+
+        match mode {
+            SelectMode::Replace => selection.set_selection(&matched, &Bitset::new_range(0, n)),
+            SelectMode::Add => selection.set_selection(&matched, &matched),
+            SelectMode::Remove => selection.set_selection(&Bitset::new_empty(), &matched),
+        };
+
+        matched.size() as usize
+    }
+
+    pub fn selected_mimetypes(&self) -> AHashSet<&'static str> {
+        Selected::from(self.visible_selection()).map(|eo| eo.get().mime).collect()
+    }
+
     pub fn create(&self, folder: bool) {
         gui_run(|g| g.create_dialog(self.id(), self.dir(), folder));
     }
@@ -1636,7 +1772,7 @@ impl Tab {
         }
 
         match kind {
-            Kind::Move(d) | Kind::Copy(d) => {
+            Kind::Move(d) | Kind::Copy(d) | Kind::Link(d) => {
                 if tab_dir == &**d {
                     true
                 } else {
@@ -1677,6 +1813,7 @@ impl Tab {
                 | Outcome::Copy(dest)
                 | Outcome::CopyOverwrite(dest)
                 | Outcome::NewFile(dest)
+                | Outcome::Symlink(dest)
                 | Outcome::CreateDestDir(dest)
                 | Outcome::MergeDestDir(dest) => {
                     if Some(tab_dir) == dest.parent() {
@@ -1792,6 +1929,7 @@ impl Tab {
                         path: eo.get().abs_path.clone(),
                         select: false,
                     }),
+                    selection: Vec::new(),
                 });
             }
 
@@ -1813,9 +1951,13 @@ impl Tab {
         self.maybe_start_apply_state();
     }
 
+    pub fn selected_entries(&self) -> Vec<EntryObject> {
+        Selected::from(self.visible_selection()).collect()
+    }
+
     pub fn context_menu(&self) -> PopoverMenu {
         info!("Spawning context menu for {:?}", self.id);
-        let sel: Vec<EntryObject> = Selected::from(self.visible_selection()).collect();
+        let sel = self.selected_entries();
 
         gui_run(|g| g.menu.get().unwrap().prepare(g, self.id(), self.settings, sel, &self.dir()))
     }
@@ -1827,6 +1969,26 @@ impl Tab {
         }
     }
 
+    pub fn ipc_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "id": self.id.raw(),
+            "path": self.dir().to_string_lossy(),
+            "search": self.search_query(),
+            "visible": self.visible(),
+        })
+    }
+
+    pub fn focus_and_settings_env_vars(&self, env: &mut Vec<(String, OsString)>) {
+        if let Some(path) = self.pane.get_visible().and_then(Pane::focused_path) {
+            env.push(("AWFM_FOCUSED".to_owned(), path.as_os_str().to_owned()));
+        }
+
+        let DirSettings { display_mode, sort } = self.settings;
+        env.push(("AWFM_DISPLAY_MODE".to_owned(), display_mode.as_ref().into()));
+        env.push(("AWFM_SORT_MODE".to_owned(), sort.mode.as_ref().into()));
+        env.push(("AWFM_SORT_DIRECTION".to_owned(), sort.direction.as_ref().into()));
+    }
+
     pub fn selection_env_str(&self) -> OsString {
         let mut selected = Selected::from(self.visible_selection());
 
@@ -1845,6 +2007,17 @@ impl Tab {
         out
     }
 
+    pub fn save_session(&self) -> SavedTab {
+        SavedTab {
+            current: self.current_history().save(),
+            past: self.past.iter().map(HistoryEntry::save).collect(),
+            future: self.future.iter().map(HistoryEntry::save).collect(),
+            selection: Selected::from(self.visible_selection())
+                .map(|eo| SavedPath(eo.get().abs_path.clone()))
+                .collect(),
+        }
+    }
+
     pub fn save_group(&self, ids: &AHashMap<TabId, u32>) -> SavedGroup {
         SavedGroup {
             parent: ids[&self.id()],
@@ -0,0 +1,117 @@
+// The client side of the IPC socket. The server runs on the GUI thread, see gui/ipc.rs.
+//
+// The protocol is one request per line, answered by one line of JSON.
+// Requests are either a plain command, run against the active tab, or a JSON object.
+
+use std::fs::read_dir;
+use std::io::{BufRead, BufReader, Write};
+use std::net::Shutdown;
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use dirs::runtime_dir;
+use serde_json::Value;
+use uzers::get_current_uid;
+
+use crate::config::OPTIONS;
+
+// The temp directory is shared between users, so the fallback is per user.
+pub fn socket_dir() -> PathBuf {
+    runtime_dir().map_or_else(
+        || std::env::temp_dir().join(format!("aw-fm-{}", get_current_uid())),
+        |dir| dir.join("aw-fm"),
+    )
+}
+
+// Another user could have created the directory first to plant or remove sockets.
+pub fn check_socket_dir(dir: &Path) -> Result<(), String> {
+    let meta = dir
+        .symlink_metadata()
+        .map_err(|e| format!("Could not read socket directory {dir:?}: {e}"))?;
+
+    if !meta.is_dir() {
+        Err(format!("Socket directory {dir:?} is not a directory"))
+    } else if meta.uid() != get_current_uid() {
+        Err(format!("Socket directory {dir:?} is owned by another user"))
+    } else if meta.mode() & 0o077 != 0 {
+        Err(format!("Socket directory {dir:?} is accessible to other users"))
+    } else {
+        Ok(())
+    }
+}
+
+pub fn socket_path(pid: u32) -> PathBuf {
+    socket_dir().join(format!("{pid}.sock"))
+}
+
+// Prefers an explicit socket, then the instance that spawned us, then the newest instance.
+fn connect() -> Result<UnixStream, String> {
+    let explicit = OPTIONS
+        .socket
+        .clone()
+        .or_else(|| std::env::var_os("AWFM_SOCKET").filter(|s| !s.is_empty()).map(Into::into));
+
+    if let Some(path) = explicit {
+        return UnixStream::connect(&path)
+            .map_err(|e| format!("Could not connect to {path:?}: {e}"));
+    }
+
+    let dir = socket_dir();
+    check_socket_dir(&dir)?;
+
+    let mut sockets: Vec<(SystemTime, PathBuf)> = read_dir(&dir)
+        .map_err(|e| format!("Could not read socket directory {dir:?}: {e}"))?
+        .filter_map(Result::ok)
+        .map(|de| de.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "sock"))
+        .filter_map(|p| Some((p.metadata().ok()?.modified().ok()?, p)))
+        .collect();
+
+    sockets.sort_by(|a, b| b.0.cmp(&a.0));
+
+    for (_, path) in sockets {
+        match UnixStream::connect(&path) {
+            Ok(stream) => return Ok(stream),
+            // Left behind by an instance that didn't exit cleanly
+            Err(e) => debug!("Skipping socket {path:?}: {e}"),
+        }
+    }
+
+    Err("Found no running aw-fm instance with ipc_socket enabled".to_string())
+}
+
+// Prints the response and returns the exit code.
+pub fn send(request: &str) -> i32 {
+    let mut stream = match connect() {
+        Ok(stream) => stream,
+        Err(e) => {
+            eprintln!("{e}");
+            return 1;
+        }
+    };
+
+    let mut line = request.replace('\n', " ");
+    line.push('\n');
+
+    if let Err(e) = stream.write_all(line.as_bytes()) {
+        eprintln!("Failed to send request: {e}");
+        return 1;
+    }
+    drop(stream.shutdown(Shutdown::Write));
+
+    let mut response = String::new();
+    if let Err(e) = BufReader::new(stream).read_line(&mut response) {
+        eprintln!("Failed to read response: {e}");
+        return 1;
+    }
+
+    let response = response.trim_end();
+    println!("{response}");
+
+    match serde_json::from_str::<Value>(response) {
+        Ok(v) if v["ok"] == Value::Bool(true) => 0,
+        _ => 1,
+    }
+}
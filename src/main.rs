@@ -19,6 +19,7 @@ mod com;
 mod config;
 mod database;
 mod gui;
+mod ipc;
 mod manager;
 mod natsort;
 mod state_cache;
@@ -45,6 +46,14 @@ fn main() {
     elapsedlogger::init_logging();
     config::init();
 
+    if let Some(request) = &config::OPTIONS.send {
+        std::process::exit(ipc::send(request));
+    }
+
+    if config::OPTIONS.check_config {
+        std::process::exit(gui::check_config());
+    }
+
     gtk::init().expect("GTK could not be initialized");
 
     let (manager_sender, manager_receiver) = tokio::sync::mpsc::unbounded_channel();
@@ -1,98 +1,305 @@
 use std::ffi::OsString;
+use std::os::unix::ffi::OsStrExt;
 use std::path::Path;
+use std::process::{ExitStatus, Output, Stdio};
 use std::sync::Arc;
 use std::time::Duration;
 
-use tokio::process::Command;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
 use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
 use tokio::{pin, select};
 
 use super::Manager;
+use super::scripts::{Pipe, Tracker, send_progress};
 use crate::closing;
-use crate::com::{ActionTarget, GuiAction};
-
+use crate::com::{ActionTarget, ExecContext, GuiAction, SelectionVia};
+use crate::config::CONFIG;
 
 impl Manager {
-    pub(super) fn execute(&self, executable: Arc<Path>, gui_env: Vec<(String, OsString)>) {
-        let cmd = match prep_command(&executable, gui_env, false) {
-            Ok(cmd) => cmd,
+    pub(super) fn execute(&self, executable: Arc<Path>, ctx: ExecContext) {
+        let (timeout, capture) = (ctx.timeout, ctx.capture_output);
+        // Dropping the child is how it gets killed after timing out
+        let (cmd, stdin) = match prep_command(&executable, ctx, timeout.is_some()) {
+            Ok(prepped) => prepped,
+            Err(e) => {
+                error!("{e}");
+                return drop(self.gui_sender.send(GuiAction::ConveyError(e)));
+            }
+        };
+
+        let tracker = self.scripts.track(&executable, capture, self.gui_sender.clone());
+        let gui_chan = self.gui_sender.clone();
+        tokio::task::spawn_local(run(cmd, stdin, capture, gui_chan, tracker, timeout));
+    }
+
+    pub(super) fn script(&self, executable: Arc<Path>, target: ActionTarget, ctx: ExecContext) {
+        let (timeout, show_output) = (ctx.timeout, ctx.capture_output);
+        let (cmd, stdin) = match prep_command(&executable, ctx, true) {
+            Ok(prepped) => prepped,
             Err(e) => {
                 error!("{e}");
                 return drop(self.gui_sender.send(GuiAction::ConveyError(e)));
             }
         };
 
-        tokio::task::spawn_local(run(cmd, self.gui_sender.clone(), true));
+        let tracker = self.scripts.track(&executable, show_output, self.gui_sender.clone());
+        tokio::task::spawn_local(run_with_output(
+            cmd,
+            stdin,
+            target,
+            self.gui_sender.clone(),
+            tracker,
+            timeout,
+        ));
     }
 
-    pub(super) fn script(
+    pub(super) fn interactive_script(
         &self,
         executable: Arc<Path>,
         target: ActionTarget,
-        gui_env: Vec<(String, OsString)>,
+        ctx: ExecContext,
     ) {
-        let cmd = match prep_command(&executable, gui_env, true) {
-            Ok(cmd) => cmd,
+        let (timeout, show_output) = (ctx.timeout, ctx.capture_output);
+        // Custom actions can't combine interactive and selection_via=stdin0
+        let (cmd, _stdin) = match prep_command(&executable, ctx, true) {
+            Ok(prepped) => prepped,
             Err(e) => {
                 error!("{e}");
                 return drop(self.gui_sender.send(GuiAction::ConveyError(e)));
             }
         };
 
-        tokio::task::spawn_local(run_with_output(cmd, target, self.gui_sender.clone()));
+        let tracker = self.scripts.track(&executable, show_output, self.gui_sender.clone());
+        tokio::task::spawn_local(run_interactive(
+            cmd,
+            target,
+            self.gui_sender.clone(),
+            tracker,
+            timeout,
+        ));
     }
 
     pub(super) fn launch(&self, executable: Arc<Path>, gui_env: Vec<(String, OsString)>) {
-        let mut cmd = match prep_command(&executable, gui_env, false) {
-            Ok(cmd) => cmd,
+        let ctx = ExecContext {
+            env: gui_env,
+            cwd: executable.parent().map(Into::into),
+            selection_via: SelectionVia::Env,
+            selection: Vec::new(),
+            terminal: false,
+            timeout: None,
+            capture_output: false,
+        };
+
+        let (mut cmd, stdin) = match prep_command(&executable, ctx, false) {
+            Ok(prepped) => prepped,
             Err(e) => {
                 error!("{e}");
                 return drop(self.gui_sender.send(GuiAction::ConveyError(e)));
             }
         };
 
-
-        if let Some(parent) = executable.parent() {
-            cmd.current_dir(parent);
+        // Launched applications aren't tracked or waited on
+        match cmd.spawn() {
+            Ok(mut child) => write_stdin(&mut child, stdin),
+            Err(e) => {
+                let msg = format!("Failed to launch {:?}: {e}", cmd.as_std().get_program());
+                error!("{msg}");
+                drop(self.gui_sender.send(GuiAction::ConveyError(msg)));
+            }
         }
+    }
+
+    pub(super) fn kill_script(&self, id: u64) {
+        self.scripts.kill(id);
+    }
 
-        tokio::task::spawn_local(run(cmd, self.gui_sender.clone(), false));
+    pub(super) fn kill_scripts(&self) {
+        self.scripts.kill_all();
     }
 }
 
+// Also returns anything that needs to be written to stdin once the command is spawned.
 fn prep_command(
     cmd: &Path,
-    env: Vec<(String, OsString)>,
+    ctx: ExecContext,
     kill_on_drop: bool,
-) -> Result<Command, String> {
-    if let Ok(canon) = cmd.canonicalize() {
-        if canon.is_relative() {
-            return Err(format!("Relative paths are not allowed, got: {cmd:?}"));
-        }
+) -> Result<(Command, Option<Vec<u8>>), String> {
+    let Ok(canon) = cmd.canonicalize() else {
+        return Err(format!("Could not get canonical path for {cmd:?}"));
+    };
+
+    if canon.is_relative() {
+        return Err(format!("Relative paths are not allowed, got: {cmd:?}"));
+    }
 
-        let mut cmd = tokio::process::Command::new(&*canon);
-        cmd.envs(env).kill_on_drop(kill_on_drop);
-        Ok(cmd)
+    let mut cmd = if ctx.terminal {
+        let Some((terminal, args)) = CONFIG.terminal.split_first() else {
+            return Err(format!("Running {cmd:?} in a terminal requires terminal to be configured"));
+        };
+
+        let mut cmd = tokio::process::Command::new(terminal);
+        cmd.args(args).arg(&*canon);
+        cmd
     } else {
-        Err(format!("Could not get canonical path for {cmd:?}"))
+        tokio::process::Command::new(&*canon)
+    };
+    cmd.envs(ctx.env).kill_on_drop(kill_on_drop);
+
+    if let Some(cwd) = ctx.cwd {
+        cmd.current_dir(&*cwd);
+    }
+
+    let stdin = match ctx.selection_via {
+        SelectionVia::Env => None,
+        SelectionVia::Args => {
+            cmd.args(ctx.selection.iter().map(|p| p.as_os_str()));
+            None
+        }
+        SelectionVia::Stdin0 => {
+            cmd.stdin(Stdio::piped());
+
+            let mut input = Vec::new();
+            for path in &ctx.selection {
+                input.extend_from_slice(path.as_os_str().as_bytes());
+                input.push(0);
+            }
+            Some(input)
+        }
+    };
+
+    Ok((cmd, stdin))
+}
+
+// Writes in the background so a child that doesn't read its stdin can't block us.
+fn write_stdin(child: &mut Child, input: Option<Vec<u8>>) {
+    let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) else {
+        return;
+    };
+
+    tokio::task::spawn_local(async move {
+        if let Err(e) = stdin.write_all(&input).await {
+            warn!("Failed to write selection to stdin of external command: {e}");
+        }
+    });
+}
+
+// Resolves to None if the timeout elapses first.
+async fn maybe_timeout<F: Future>(timeout: Option<Duration>, fut: F) -> Option<F::Output> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, fut).await.ok(),
+        None => Some(fut.await),
+    }
+}
+
+enum Stopped {
+    TimedOut,
+    Killed,
+}
+
+// Resolves to the output of fut unless it times out or the user kills the process first.
+async fn supervise<F: Future>(
+    timeout: Option<Duration>,
+    tracker: &mut Tracker,
+    fut: F,
+) -> Result<F::Output, Stopped> {
+    select! {
+        output = maybe_timeout(timeout, fut) => output.ok_or(Stopped::TimedOut),
+        _ = tracker.killed() => Err(Stopped::Killed),
+    }
+}
+
+// Tracked commands lead their own process group, so anything they started can be killed with them.
+fn kill_group(cmd: &Command, pid: Option<u32>) {
+    // The child has already been waited on, so its process group may have been reused
+    let Some(pid) = pid else {
+        return;
+    };
+
+    let ret = unsafe { libc::kill(-(pid as libc::pid_t), libc::SIGKILL) };
+    if ret != 0 {
+        error!("Failed to kill {cmd:?}: {}", std::io::Error::last_os_error());
+    }
+}
+
+fn stop(
+    cmd: &Command,
+    child: &mut Child,
+    stopped: Stopped,
+    timeout: Option<Duration>,
+    gui_chan: &UnboundedSender<GuiAction>,
+) {
+    kill_group(cmd, child.id());
+
+    match stopped {
+        Stopped::TimedOut => {
+            let timeout = timeout.unwrap_or_default();
+            let msg = format!("Executable {cmd:?} timed out after {timeout:?} and was killed");
+            error!("{msg}");
+            drop(gui_chan.send(GuiAction::ConveyError(msg)));
+        }
+        Stopped::Killed => info!("Killed {cmd:?}"),
     }
 }
 
 async fn run_with_output(
     mut cmd: Command,
+    stdin: Option<Vec<u8>>,
     mut target_tab: ActionTarget,
     gui_chan: UnboundedSender<GuiAction>,
+    mut tracker: Tracker,
+    timeout: Option<Duration>,
 ) {
-    let fut = cmd.output();
-
-    pin!(fut);
-    let output = select! {
-        output = &mut fut => output,
-        _ = closing::closed_fut() => {
-            warn!("Waiting to exit for up to 60 seconds until external command completes: {cmd:?}");
-            drop(tokio::time::timeout(Duration::from_secs(60), fut).await);
-            return warn!("Command blocking exit completed or killed: {cmd:?}");
-        },
+    if stdin.is_none() {
+        cmd.stdin(Stdio::null());
+    }
+    cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).process_group(0);
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            let msg = format!("Executable {cmd:?} failed to start with error {e}");
+            error!("{msg}");
+            return drop(gui_chan.send(GuiAction::ConveyError(msg)));
+        }
+    };
+    let pid = child.id();
+    tracker.started();
+    write_stdin(&mut child, stdin);
+
+    let stdout = tracker.forward(child.stdout.take().unwrap(), Pipe::Commands);
+    let stderr = tracker.forward(child.stderr.take().unwrap(), Pipe::Kept);
+
+    let output = {
+        let wait = async {
+            let status = child.wait().await?;
+            let stdout = stdout.await.unwrap_or_default();
+            let stderr = stderr.await.unwrap_or_default();
+            Ok::<_, std::io::Error>(Output { status, stdout, stderr })
+        };
+
+        let fut = supervise(timeout, &mut tracker, wait);
+        pin!(fut);
+        select! {
+            output = &mut fut => output,
+            _ = closing::closed_fut() => {
+                warn!(
+                    "Waiting to exit for up to 60 seconds until external command completes: \
+                     {cmd:?}"
+                );
+                if tokio::time::timeout(Duration::from_secs(60), fut).await.is_err() {
+                    kill_group(&cmd, pid);
+                }
+                return warn!("Command blocking exit completed or killed: {cmd:?}");
+            },
+        }
+    };
+
+    let output = match output {
+        Ok(output) => output,
+        Err(stopped) => return stop(&cmd, &mut child, stopped, timeout, &gui_chan),
     };
 
     match output {
@@ -124,15 +331,139 @@ async fn run_with_output(
             info!("stdout: {:?}", String::from_utf8_lossy(&output.stdout));
             warn!("stderr: {:?}", String::from_utf8_lossy(&output.stderr));
         }
+        Err(e) => {
+            let msg = format!("Executable {cmd:?} failed with error {e}");
+            error!("{msg}");
+            drop(gui_chan.send(GuiAction::ConveyError(msg)));
+        }
+    }
+}
+
+async fn run_interactive(
+    mut cmd: Command,
+    target_tab: ActionTarget,
+    gui_chan: UnboundedSender<GuiAction>,
+    mut tracker: Tracker,
+    timeout: Option<Duration>,
+) {
+    cmd.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).process_group(0);
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
         Err(e) => {
             let msg = format!("Executable {cmd:?} failed to start with error {e}");
             error!("{msg}");
+            return drop(gui_chan.send(GuiAction::ConveyError(msg)));
+        }
+    };
+    let pid = child.id();
+    tracker.started();
+
+    // stdout is reserved for commands, so only stderr is shown while it runs
+    let stderr = tracker.forward(child.stderr.take().unwrap(), Pipe::Kept);
+
+    let output = {
+        let interaction = interact(&mut child, stderr, tracker.id(), target_tab, &gui_chan);
+        let fut = supervise(timeout, &mut tracker, interaction);
+        pin!(fut);
+        select! {
+            output = &mut fut => output,
+            _ = closing::closed_fut() => {
+                warn!(
+                    "Waiting to exit for up to 60 seconds until external command completes: \
+                     {cmd:?}"
+                );
+                if tokio::time::timeout(Duration::from_secs(60), fut).await.is_err() {
+                    kill_group(&cmd, pid);
+                }
+                return warn!("Command blocking exit completed or killed: {cmd:?}");
+            },
+        }
+    };
+
+    let output = match output {
+        Ok(output) => output,
+        Err(stopped) => return stop(&cmd, &mut child, stopped, timeout, &gui_chan),
+    };
+
+    match output {
+        Ok((status, _)) if status.success() => {}
+        Ok((status, stderr)) => {
+            let msg = format!("Executable {cmd:?} exited with error code {status:?}");
+            error!("{msg}");
+            drop(gui_chan.send(GuiAction::ConveyError(msg)));
+
+            warn!("stderr: {:?}", String::from_utf8_lossy(&stderr));
+        }
+        Err(e) => {
+            let msg = format!("Executable {cmd:?} failed with error {e}");
+            error!("{msg}");
             drop(gui_chan.send(GuiAction::ConveyError(msg)));
         }
     }
 }
 
-async fn run(mut cmd: Command, gui_chan: UnboundedSender<GuiAction>, convey_errors: bool) {
+// Runs each line of output as soon as it's read and writes the response back to stdin.
+// Returns the exit status and anything written to stderr.
+async fn interact(
+    child: &mut Child,
+    stderr: JoinHandle<Vec<u8>>,
+    id: u64,
+    mut target_tab: ActionTarget,
+    gui_chan: &UnboundedSender<GuiAction>,
+) -> std::io::Result<(ExitStatus, Vec<u8>)> {
+    let mut stdin = child.stdin.take();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap()).lines();
+
+    while let Some(line) = stdout.next_line().await? {
+        let mut response = if line == "ClearTarget" || line == "ClearTargetTab" {
+            info!("Clearing script target tab, was {target_tab:?}");
+            target_tab = ActionTarget::Active;
+            r#"{"ok":true}"#.to_string()
+        } else if send_progress(id, &line, gui_chan) {
+            r#"{"ok":true}"#.to_string()
+        } else {
+            info!("Running interactive command from script: {line}");
+            let (send, recv) = oneshot::channel();
+            if gui_chan.send(GuiAction::InteractiveAction(line, target_tab, send)).is_err() {
+                break;
+            }
+
+            // Only fails if the GUI has gone away
+            let Ok(response) = recv.await else {
+                break;
+            };
+            response
+        };
+        response.push('\n');
+
+        if let Some(input) = &mut stdin
+            && let Err(e) = input.write_all(response.as_bytes()).await
+        {
+            // Scripts are free to close stdin if they don't care about the responses
+            debug!("Stopped writing responses to interactive script: {e}");
+            stdin = None;
+        }
+    }
+
+    drop(stdin);
+    let status = child.wait().await?;
+    Ok((status, stderr.await.unwrap_or_default()))
+}
+
+async fn run(
+    mut cmd: Command,
+    stdin: Option<Vec<u8>>,
+    capture: bool,
+    gui_chan: UnboundedSender<GuiAction>,
+    mut tracker: Tracker,
+    timeout: Option<Duration>,
+) {
+    if capture {
+        cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    }
+    cmd.process_group(0);
+
     let mut child = match cmd.spawn() {
         Ok(child) => child,
         // Always convey this error
@@ -142,20 +473,39 @@ async fn run(mut cmd: Command, gui_chan: UnboundedSender<GuiAction>, convey_erro
             return drop(gui_chan.send(GuiAction::ConveyError(msg)));
         }
     };
+    let pid = child.id();
+    tracker.started();
+    write_stdin(&mut child, stdin);
 
-    if !convey_errors {
-        return;
+    // Only shown in the scripts panel, nothing else needs the output
+    if let (Some(stdout), Some(stderr)) = (child.stdout.take(), child.stderr.take()) {
+        drop(tracker.forward(stdout, Pipe::Shown));
+        drop(tracker.forward(stderr, Pipe::Shown));
     }
 
-    let fut = child.wait();
-    pin!(fut);
-    let status = select! {
-        status = &mut fut => status,
-        _ = closing::closed_fut() => {
-            warn!("Waiting to exit for up to 60 seconds until external command completes: {cmd:?}");
-            drop(tokio::time::timeout(Duration::from_secs(60), fut).await);
-            return warn!("Command blocking exit completed or killed: {cmd:?}");
-        },
+    let status = {
+        let fut = supervise(timeout, &mut tracker, child.wait());
+        pin!(fut);
+        select! {
+            status = &mut fut => status,
+            _ = closing::closed_fut() => {
+                warn!(
+                    "Waiting to exit for up to 60 seconds until external command completes: \
+                     {cmd:?}"
+                );
+                let waited = tokio::time::timeout(Duration::from_secs(60), fut).await;
+                // Only commands with a timeout are killed when dropped
+                if waited.is_err() && timeout.is_some() {
+                    kill_group(&cmd, pid);
+                }
+                return warn!("Command blocking exit completed or killed: {cmd:?}");
+            },
+        }
+    };
+
+    let status = match status {
+        Ok(status) => status,
+        Err(stopped) => return stop(&cmd, &mut child, stopped, timeout, &gui_chan),
     };
 
     match status {
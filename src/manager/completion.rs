@@ -6,17 +6,17 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, LazyLock};
 use std::time::Instant;
 
+use ahash::AHashMap;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use rayon::slice::ParallelSliceMut;
 use rayon::{ThreadPool, ThreadPoolBuilder};
 use regex::Regex;
 use tokio::sync::oneshot::{self, Receiver};
 
-use crate::com::CompletionResult;
+use crate::com::{CompletionResult, CompletionTarget};
 use crate::config::CONFIG;
-use crate::gui::TabId;
 use crate::handle_panic;
-use crate::natsort::{lowercase, normalize_lowercase};
+use crate::natsort::{self, lowercase, normalize_lowercase};
 
 static COMPLETION_POOL: LazyLock<ThreadPool> = LazyLock::new(|| {
     ThreadPoolBuilder::new()
@@ -167,7 +167,8 @@ fn flatten_candidates(
 pub(super) fn complete(
     path: PathBuf,
     initial: String,
-    tab: TabId,
+    target: CompletionTarget,
+    recent_dirs: Arc<[(Arc<Path>, f64)]>,
 ) -> (Receiver<CompletionResult>, Arc<AtomicBool>) {
     let start = Instant::now();
     let (send, recv) = oneshot::channel();
@@ -245,8 +246,26 @@ pub(super) fn complete(
             return None;
         }
 
-        candidates.as_mut_slice().par_sort();
-        let mut candidates: Vec<_> = candidates.into_iter().map(|(_, p)| p).collect();
+        let frecency: AHashMap<&Path, f64> = recent_dirs.iter().map(|(p, s)| (&**p, *s)).collect();
+
+        // Exact matches first, then visited directories by frecency, then by how well they
+        // matched. Natural sorting breaks ties so the order is stable between attempts.
+        let mut candidates: Vec<_> = candidates
+            .into_par_iter()
+            .map(|(priority, p)| {
+                let score = frecency.get(p.as_path()).copied().unwrap_or_default();
+                (priority, score, natsort::key(p.as_os_str()), p)
+            })
+            .collect();
+
+        candidates.par_sort_by(|a, b| {
+            (a.0 != 0)
+                .cmp(&(b.0 != 0))
+                .then_with(|| b.1.total_cmp(&a.1))
+                .then_with(|| a.0.cmp(&b.0))
+                .then_with(|| a.2.cmp(&b.2))
+        });
+        let mut candidates: Vec<_> = candidates.into_iter().map(|(.., p)| p).collect();
 
 
         // Allow the user to cycle back to whatever they initially entered
@@ -256,7 +275,7 @@ pub(super) fn complete(
 
         let position = if candidates[0] == path { 1 } else { 0 };
 
-        Some(CompletionResult { tab, initial, candidates, position })
+        Some(CompletionResult { target, initial, candidates, position })
     };
 
     COMPLETION_POOL.spawn(move || {
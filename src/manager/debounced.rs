@@ -0,0 +1,105 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ahash::AHashSet;
+use notify::RecursiveMode::NonRecursive;
+use notify::{Event, EventKind, RecommendedWatcher, Watcher};
+use tokio::select;
+use tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
+use tokio::time::{Instant, sleep_until};
+
+use crate::closing;
+
+// Editors tend to write files in several steps
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+// Watches the direct children of a directory, or a single file in it, for the custom actions
+// directory and the config file.
+// This is kept separate from the main watcher so it can't interfere with tabs open in the same
+// directory.
+#[derive(Debug)]
+pub(super) struct DebouncedWatcher {
+    _watcher: RecommendedWatcher,
+    receiver: UnboundedReceiver<notify::Result<Event>>,
+    dir: Arc<Path>,
+    // Editors often replace files, so files are watched through their directory
+    file: Option<Arc<Path>>,
+
+    pending: AHashSet<Arc<Path>>,
+    deadline: Option<Instant>,
+}
+
+impl DebouncedWatcher {
+    pub(super) fn new(dir: Arc<Path>, file: Option<Arc<Path>>) -> Option<Self> {
+        let (sender, receiver) = unbounded_channel();
+
+        let mut watcher = notify::recommended_watcher(move |res| {
+            if let Err(e) = sender.send(res)
+                && !closing::closed()
+            {
+                closing::fatal(format!("Error sending from debounced watcher: {e}"));
+            }
+        })
+        .unwrap();
+
+        if let Err(e) = watcher.watch(&dir, NonRecursive) {
+            warn!("Not watching {:?} for changes: {e}", file.as_ref().unwrap_or(&dir));
+            return None;
+        }
+
+        Some(Self {
+            _watcher: watcher,
+            receiver,
+            dir,
+            file,
+            pending: AHashSet::new(),
+            deadline: None,
+        })
+    }
+
+    // Resolves to the changed paths once things have been quiet for a bit.
+    // Safe to cancel, pending changes are kept until the next call.
+    pub(super) async fn changed(&mut self) -> Vec<Arc<Path>> {
+        loop {
+            let deadline = self.deadline;
+
+            select! {
+                ev = self.receiver.recv() => {
+                    // The sender lives in the watcher we're holding
+                    match ev.unwrap() {
+                        Ok(ev) => self.push(ev),
+                        Err(e) => error!("Error watching {:?}: {e}", self.dir),
+                    }
+                }
+                _ = async { sleep_until(deadline.unwrap()).await }, if deadline.is_some() => {
+                    self.deadline = None;
+                    return self.pending.drain().collect();
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, ev: Event) {
+        if matches!(ev.kind, EventKind::Access(_) | EventKind::Other) {
+            return;
+        }
+
+        // Changes to the directory itself don't matter
+        let changed: Vec<_> = ev
+            .paths
+            .into_iter()
+            .filter(|p| match &self.file {
+                Some(file) => p == &**file,
+                None => p.parent() == Some(&*self.dir),
+            })
+            .collect();
+        if changed.is_empty() {
+            return;
+        }
+
+        trace!("Changed in {:?}: {changed:?}", self.dir);
+        self.pending.extend(changed.into_iter().map(Into::into));
+        self.deadline = Some(Instant::now() + DEBOUNCE);
+    }
+}
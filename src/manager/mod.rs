@@ -2,6 +2,7 @@ use std::collections::{BTreeMap, BTreeSet};
 use std::future::Future;
 use std::panic::{AssertUnwindSafe, catch_unwind};
 use std::path::Path;
+use std::rc::Rc;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread::JoinHandle;
@@ -16,15 +17,19 @@ use tokio::sync::oneshot::Receiver;
 use tokio::task::LocalSet;
 use tokio::time::{Instant, sleep_until, timeout};
 
+use self::debounced::DebouncedWatcher;
+use self::scripts::RunningScripts;
 use self::watcher::PendingUpdates;
 use crate::com::{CompletionResult, GuiAction, ManagerAction};
-use crate::config::{CONFIG, NfsPolling, OPTIONS};
+use crate::config::{ACTIONS_DIR, CONFIG, NfsPolling, OPTIONS};
 use crate::manager::watcher::Sources;
 use crate::{closing, spawn_thread};
 
 mod actions;
 mod completion;
+mod debounced;
 mod read_dir;
+mod scripts;
 mod watcher;
 
 type RecurseId = Arc<AtomicBool>;
@@ -55,6 +60,10 @@ struct Manager {
     notify_receiver: UnboundedReceiver<(notify::Result<Event>, Option<RecurseId>)>,
 
     completion: Option<(Receiver<CompletionResult>, Arc<AtomicBool>)>,
+
+    scripts: Rc<RunningScripts>,
+    action_watcher: Option<DebouncedWatcher>,
+    config_watcher: Option<DebouncedWatcher>,
 }
 
 pub fn run(
@@ -146,9 +155,30 @@ impl Manager {
             notify_receiver,
 
             completion: None,
+
+            scripts: Rc::default(),
+            action_watcher: DebouncedWatcher::new(ACTIONS_DIR.as_path().into(), None),
+            config_watcher: Self::watch_config(),
         }
     }
 
+    fn watch_config() -> Option<DebouncedWatcher> {
+        if !CONFIG.watch_config || OPTIONS.chooser_mode.is_some() {
+            return None;
+        }
+
+        // Resolves symlinks, so the file that actually gets edited is watched
+        let path = match CONFIG.path.as_ref()?.canonicalize() {
+            Ok(path) => path,
+            Err(e) => {
+                warn!("Not watching config file {:?}: {e}", CONFIG.path);
+                return None;
+            }
+        };
+
+        DebouncedWatcher::new(path.parent()?.into(), Some(path.into()))
+    }
+
     async fn run(mut self, mut receiver: UnboundedReceiver<ManagerAction>) {
         // NFS times out after 5 minutes of idleness, so stat every 4.5 minutes
         const NFS_KEEPALIVE_PERIOD: Duration = Duration::from_secs(60 * 9 / 2);
@@ -191,6 +221,14 @@ impl Manager {
                         if self.next_tick.is_some() => {
                     self.handle_pending_updates();
                 }
+                changed = async { self.action_watcher.as_mut().unwrap().changed().await },
+                        if self.action_watcher.is_some() => {
+                    self.send(GuiAction::CustomActionsChanged(changed));
+                }
+                _ = async { self.config_watcher.as_mut().unwrap().changed().await },
+                        if self.config_watcher.is_some() => {
+                    self.send(GuiAction::ConfigChanged);
+                }
                 _ = sleep_until(next_keepalive), if !self.nfs_keepalives.is_empty() => {
                     next_keepalive = Instant::now() + NFS_KEEPALIVE_PERIOD;
 
@@ -231,15 +269,18 @@ impl Manager {
             }
             Unwatch(path) => self.unwatch_dir(&path),
 
-            Search(path, cancel) => {
-                self.watch_search(path.clone(), cancel.clone()).await;
-                self.recurse_dir(path, cancel);
+            Search(path, options, cancel) => {
+                self.watch_search(path.clone(), options.max_depth, cancel.clone()).await;
+                self.recurse_dir(path, options, cancel);
             }
-            EndSearch(cancel) => self.unwatch_search(cancel),
+            EndSearch(cancel, options) => self.unwatch_search(cancel, options.max_depth),
 
-            Execute(s, env) => self.execute(s, env),
-            Script(s, target, env) => self.script(s, target, env),
+            Execute(s, ctx) => self.execute(s, ctx),
+            Script(s, target, ctx) => self.script(s, target, ctx),
+            InteractiveScript(s, target, ctx) => self.interactive_script(s, target, ctx),
             Launch(s, env) => self.launch(s, env),
+            KillScript(id) => self.kill_script(id),
+            KillScripts => self.kill_scripts(),
 
             GetChildren(dirs, cancel) => self.get_children(dirs, cancel),
 
@@ -258,8 +299,9 @@ impl Manager {
                 let _ignored = finished.send(all_paths);
             }
 
-            Complete(path, initial, tab) => {
-                if let Some((_, cancel)) = self.completion.replace(complete(path, initial, tab)) {
+            Complete(path, initial, target, recent_dirs) => {
+                let completion = complete(path, initial, target, recent_dirs);
+                if let Some((_, cancel)) = self.completion.replace(completion) {
                     cancel.store(true, Ordering::Relaxed);
                 }
             }
@@ -26,10 +26,9 @@ use tokio::time::{Instant, sleep, sleep_until};
 
 use super::Manager;
 use crate::com::{
-    ChildInfo, DirSnapshot, Entry, GuiAction, SearchSnapshot, SearchUpdate, SnapshotKind,
-    SortSettings, Update,
+    ChildInfo, DirSnapshot, Entry, GuiAction, SearchOptions, SearchSnapshot, SearchUpdate,
+    SnapshotKind, SortSettings, Update,
 };
-use crate::config::CONFIG;
 use crate::{closing, handle_panic};
 
 #[cfg(not(feature = "debug-forced-slow"))]
@@ -213,23 +212,24 @@ fn read_dir_sync(
 
 fn recurse_dir_sync(
     root: Arc<Path>,
+    options: SearchOptions,
     cancel: Arc<AtomicBool>,
     sender: UnboundedSender<ReadResult>,
     gui_sender: UnboundedSender<GuiAction>,
 ) -> oneshot::Receiver<()> {
     let (send_done, recv_done) = oneshot::channel();
 
-    if Some(0) == CONFIG.search_max_depth {
+    if Some(0) == options.max_depth {
         let _ignored = send_done.send(());
         return recv_done;
     }
 
     READ_POOL.spawn(move || {
-        let show_all = CONFIG.search_show_all;
+        let show_all = options.show_all;
         let walker = WalkBuilder::new(&root)
             // Uncertain.
             .follow_links(true)
-            .max_depth(CONFIG.search_max_depth.map(|n|n as usize + 1))
+            .max_depth(options.max_depth.map(|n|n as usize + 1))
             .hidden(!show_all)
             .ignore(!show_all)
             .git_ignore(!show_all)
@@ -338,6 +338,7 @@ async fn read_dir(
 
 async fn recurse_dir(
     path: Arc<Path>,
+    options: SearchOptions,
     cancel: Arc<AtomicBool>,
     gui_sender: UnboundedSender<GuiAction>,
 ) {
@@ -346,7 +347,7 @@ async fn recurse_dir(
     let start = Instant::now();
     let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
 
-    let h = recurse_dir_sync(path.clone(), cancel.clone(), sender, gui_sender.clone());
+    let h = recurse_dir_sync(path.clone(), options, cancel.clone(), sender, gui_sender.clone());
 
     consume_entries(path.clone(), cancel.clone(), gui_sender, receiver, search_snap).await;
 
@@ -859,8 +860,13 @@ impl Manager {
         spawn_local(read_dir(path, cancel, sort, self.gui_sender.clone()));
     }
 
-    pub(super) fn recurse_dir(&self, path: Arc<Path>, cancel: Arc<AtomicBool>) {
-        spawn_local(recurse_dir(path, cancel, self.gui_sender.clone()));
+    pub(super) fn recurse_dir(
+        &self,
+        path: Arc<Path>,
+        options: SearchOptions,
+        cancel: Arc<AtomicBool>,
+    ) {
+        spawn_local(recurse_dir(path, options, cancel, self.gui_sender.clone()));
     }
 
     pub(super) fn get_children(&self, dirs: Vec<Arc<Path>>, cancel: Arc<AtomicBool>) {
@@ -0,0 +1,246 @@
+use std::cell::{Cell, RefCell};
+use std::path::Path;
+use std::rc::Rc;
+
+use ahash::AHashMap;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+use crate::com::GuiAction;
+
+// Processes started by Execute, Script and custom actions, so the user can kill them.
+#[derive(Debug, Default)]
+pub(super) struct RunningScripts {
+    next_id: Cell<u64>,
+    kill: RefCell<AHashMap<u64, oneshot::Sender<()>>>,
+}
+
+impl RunningScripts {
+    pub(super) fn track(
+        self: &Rc<Self>,
+        executable: &Path,
+        show_output: bool,
+        gui_chan: UnboundedSender<GuiAction>,
+    ) -> Tracker {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+
+        let (kill, killed) = oneshot::channel();
+        self.kill.borrow_mut().insert(id, kill);
+
+        let name = executable.file_name().unwrap_or(executable.as_os_str());
+
+        Tracker {
+            id,
+            name: name.to_string_lossy().into_owned(),
+            started: false,
+            show_output,
+            killed,
+            scripts: self.clone(),
+            gui_chan,
+        }
+    }
+
+    pub(super) fn kill(&self, id: u64) {
+        match self.kill.borrow_mut().remove(&id) {
+            Some(kill) => drop(kill.send(())),
+            None => debug!("Not killing script {id}, it has already exited"),
+        }
+    }
+
+    pub(super) fn kill_all(&self) {
+        for (_, kill) in self.kill.borrow_mut().drain() {
+            drop(kill.send(()));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Pipe {
+    // Only shown in the scripts panel
+    Shown,
+    // Also returned once the pipe closes
+    Kept,
+    // The stdout of a Script. Progress and Status lines are sent immediately, the rest is kept.
+    Commands,
+}
+
+// Shows a running process in the GUI until it is dropped.
+#[derive(Debug)]
+pub(super) struct Tracker {
+    id: u64,
+    name: String,
+    started: bool,
+    // Only while the scripts panel was open when it started, nobody would see it otherwise
+    show_output: bool,
+    killed: oneshot::Receiver<()>,
+    scripts: Rc<RunningScripts>,
+    gui_chan: UnboundedSender<GuiAction>,
+}
+
+impl Tracker {
+    pub(super) const fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub(super) fn started(&mut self) {
+        self.started = true;
+        drop(self.gui_chan.send(GuiAction::ScriptStarted(self.id, self.name.clone())));
+    }
+
+    // Must not be awaited again after it resolves.
+    pub(super) async fn killed(&mut self) {
+        drop((&mut self.killed).await);
+    }
+
+    // Sends each line to the GUI as it's read, if the output is shown.
+    // Unless it's only shown, the handle resolves to everything that was read.
+    pub(super) fn forward<R: AsyncRead + Unpin + 'static>(
+        &self,
+        pipe: R,
+        kind: Pipe,
+    ) -> JoinHandle<Vec<u8>> {
+        let (id, show, gui_chan) = (self.id, self.show_output, self.gui_chan.clone());
+
+        tokio::task::spawn_local(async move {
+            let mut kept = Vec::new();
+            let mut lines = BufReader::new(pipe).split(b'\n');
+
+            loop {
+                let line = match lines.next_segment().await {
+                    Ok(Some(line)) => line,
+                    Ok(None) => break,
+                    Err(e) => {
+                        warn!("Failed to read output of script {id}: {e}");
+                        break;
+                    }
+                };
+
+                let text = String::from_utf8_lossy(&line).into_owned();
+
+                if kind == Pipe::Commands && send_progress(id, &text, &gui_chan) {
+                    continue;
+                }
+
+                if show {
+                    debug!("Script {id}: {text}");
+                    drop(gui_chan.send(GuiAction::ScriptOutput(id, text)));
+                }
+
+                if kind != Pipe::Shown {
+                    kept.extend_from_slice(&line);
+                    kept.push(b'\n');
+                }
+            }
+
+            kept
+        })
+    }
+}
+
+impl Drop for Tracker {
+    fn drop(&mut self) {
+        self.scripts.kill.borrow_mut().remove(&self.id);
+
+        if self.started {
+            drop(self.gui_chan.send(GuiAction::ScriptFinished(self.id)));
+        }
+    }
+}
+
+// Sends Progress and Status lines to the GUI, returning whether the line was one of them.
+pub(super) fn send_progress(id: u64, line: &str, gui_chan: &UnboundedSender<GuiAction>) -> bool {
+    let Some((fraction, message)) = parse_progress(id, line, gui_chan) else {
+        return false;
+    };
+
+    drop(gui_chan.send(GuiAction::ScriptProgress(id, fraction, message)));
+    true
+}
+
+// "Progress <fraction> [message]" or "Status <message>".
+// A malformed Progress line is reported, but still never run as a command.
+fn parse_progress(
+    id: u64,
+    line: &str,
+    gui_chan: &UnboundedSender<GuiAction>,
+) -> Option<(Option<f64>, String)> {
+    let line = line.trim();
+    let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+    let rest = rest.trim();
+
+    match keyword {
+        "Status" => return Some((None, rest.to_string())),
+        "Progress" => {}
+        _ => return None,
+    }
+
+    let (fraction, message) = rest.split_once(' ').unwrap_or((rest, ""));
+    let message = message.trim().to_string();
+
+    match fraction.parse::<f64>() {
+        Ok(f) if (0.0..=1.0).contains(&f) => Some((Some(f), message)),
+        _ => {
+            let msg = format!("Script {id} sent an invalid progress fraction {fraction:?}");
+            warn!("{msg}");
+            drop(gui_chan.send(GuiAction::ConveyWarning(msg)));
+            Some((None, message))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::unbounded_channel;
+
+    use super::parse_progress;
+    use crate::com::GuiAction;
+
+    fn parse(line: &str) -> (Option<(Option<f64>, String)>, bool) {
+        let (send, mut recv) = unbounded_channel();
+        let parsed = parse_progress(0, line, &send);
+        let warned = matches!(recv.try_recv(), Ok(GuiAction::ConveyWarning(_)));
+        (parsed, warned)
+    }
+
+    fn progress(fraction: Option<f64>, message: &str) -> Option<(Option<f64>, String)> {
+        Some((fraction, message.to_string()))
+    }
+
+    #[test]
+    fn status() {
+        assert_eq!(parse("Status Preparing"), (progress(None, "Preparing"), false));
+        assert_eq!(parse("  Status   Two words  "), (progress(None, "Two words"), false));
+        assert_eq!(parse("Status"), (progress(None, ""), false));
+    }
+
+    #[test]
+    fn fractions() {
+        assert_eq!(parse("Progress 0"), (progress(Some(0.0), ""), false));
+        assert_eq!(parse("Progress 1"), (progress(Some(1.0), ""), false));
+        assert_eq!(
+            parse("Progress 0.5 Halfway there"),
+            (progress(Some(0.5), "Halfway there"), false)
+        );
+        assert_eq!(parse("Progress  0.25   Spaced "), (progress(Some(0.25), "Spaced"), false));
+    }
+
+    #[test]
+    fn malformed_fractions() {
+        assert_eq!(parse("Progress 1.5 Too far"), (progress(None, "Too far"), true));
+        assert_eq!(parse("Progress -0.1"), (progress(None, ""), true));
+        assert_eq!(parse("Progress NaN"), (progress(None, ""), true));
+        assert_eq!(parse("Progress half Message"), (progress(None, "Message"), true));
+        assert_eq!(parse("Progress"), (progress(None, ""), true));
+    }
+
+    #[test]
+    fn commands() {
+        assert_eq!(parse("NewTab"), (None, false));
+        assert_eq!(parse("Navigate /Progress 0.5"), (None, false));
+        assert_eq!(parse("Progressive 0.5"), (None, false));
+        assert_eq!(parse("StatusBar"), (None, false));
+    }
+}
@@ -140,13 +140,13 @@ impl Manager {
         }
     }
 
-    pub(super) fn unwatch_search(&mut self, cancel: RecurseId) {
+    pub(super) fn unwatch_search(&mut self, cancel: RecurseId, max_depth: Option<u8>) {
         let pos = self.open_searches.iter().position(|(id, _watcher)| Arc::ptr_eq(&cancel, id));
         if let Some(pos) = pos {
             debug!("Removing recursive search watcher");
             let (_, watcher) = self.open_searches.swap_remove(pos);
             drop(watcher);
-        } else if Some(0) != CONFIG.search_max_depth {
+        } else if Some(0) != max_depth {
             error!("Stopped watching non-existent search. Updates were broken.");
         }
     }
@@ -417,8 +417,13 @@ impl Manager {
         unmatched_paths
     }
 
-    pub(super) async fn watch_search(&mut self, path: Arc<Path>, cancel: RecurseId) {
-        if Some(0) == CONFIG.search_max_depth {
+    pub(super) async fn watch_search(
+        &mut self,
+        path: Arc<Path>,
+        max_depth: Option<u8>,
+        cancel: RecurseId,
+    ) {
+        if Some(0) == max_depth {
             return;
         }
 
@@ -441,7 +446,7 @@ impl Manager {
                     return;
                 }
 
-                if let Some(depth) = CONFIG.search_max_depth {
+                if let Some(depth) = max_depth {
                     match parent.strip_prefix(&search_root) {
                         Ok(dirs) => {
                             if dirs.components().count() > depth as usize {